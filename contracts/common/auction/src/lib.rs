@@ -0,0 +1,262 @@
+//! Dutch auction pricing shared by the CosmWasm and Soroban resolvers, so an order quotes the
+//! same price on every chain.
+//!
+//! Prices are 18-decimal fixed point. Products are taken at 256 bits before dividing back down,
+//! so only a result that does not fit in 128 bits fails, with [`Error::Overflow`].
+
+#![no_std]
+
+pub mod vectors;
+
+/// Fixed-point precision of auction prices (18 decimals)
+pub const PRICE_PRECISION: u128 = 1_000_000_000_000_000_000;
+
+/// Denominator of piecewise-linear rate bumps: 10_000_000 is +100% over `end_price`
+pub const RATE_BUMP_DENOMINATOR: u32 = 10_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The auction starts after the quoted time
+    NotStarted,
+    /// The auction, or a curve segment, ends before it starts
+    InvalidDuration,
+    /// The curve would raise the price over time
+    InvalidPrice,
+    /// The result does not fit in 128 bits
+    Overflow,
+}
+
+/// Point of a piecewise-linear curve, `delay` seconds after the previous one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Point {
+    pub delay: u32,
+    pub rate_bump: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curve<'a> {
+    /// Straight from `start_price` down to `end_price`
+    Linear,
+    /// `end_price` bumped by a rate interpolated between the points, and no bump at the end
+    PiecewiseLinear { initial_rate_bump: u32, points: &'a [Point] },
+    /// Halves the gap down to `end_price` every `half_life` seconds. This approximates an
+    /// exponential decay: within each half-life the price falls linearly to the next halving,
+    /// and whatever gap is left drops to `end_price` at `end_time`.
+    Exponential { half_life: u64 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Auction<'a> {
+    pub start_price: u128,
+    pub end_price: u128,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub curve: Curve<'a>,
+}
+
+impl Auction<'_> {
+    /// Price at `now`, `end_price` once the auction is over
+    pub fn price(&self, now: u64) -> Result<u128, Error> {
+        if now < self.start_time {
+            return Err(Error::NotStarted);
+        }
+
+        match self.curve {
+            Curve::Linear => self.linear_price(now),
+            Curve::PiecewiseLinear { initial_rate_bump, points } => {
+                self.piecewise_price(initial_rate_bump, points, now)
+            }
+            Curve::Exponential { half_life } => self.exponential_price(half_life, now),
+        }
+    }
+
+    /// Taking amount for `making_amount` at the price at `now`
+    pub fn taking_amount(&self, making_amount: u128, now: u64) -> Result<u128, Error> {
+        mul_div(making_amount, self.price(now)?, PRICE_PRECISION).ok_or(Error::Overflow)
+    }
+
+    fn linear_price(&self, now: u64) -> Result<u128, Error> {
+        if now >= self.end_time {
+            return Ok(self.end_price);
+        }
+        if self.start_price <= self.end_price {
+            return Err(Error::InvalidPrice);
+        }
+
+        let price_decrease = self.start_price - self.end_price;
+        let price_reduction = mul_div(
+            price_decrease,
+            u128::from(now - self.start_time),
+            u128::from(self.end_time - self.start_time),
+        )
+        .ok_or(Error::Overflow)?;
+        Ok(self.start_price - price_reduction)
+    }
+
+    fn piecewise_price(&self, initial_rate_bump: u32, points: &[Point], now: u64) -> Result<u128, Error> {
+        if self.end_time <= self.start_time {
+            return Err(Error::InvalidDuration);
+        }
+
+        // Every point is checked, even past `now`, so the curve is valid at any time
+        let mut from = (self.start_time, initial_rate_bump);
+        let mut rate_bump = None;
+        for point in points {
+            let to = (from.0.saturating_add(u64::from(point.delay)), point.rate_bump);
+            if to.0 > self.end_time {
+                return Err(Error::InvalidDuration);
+            }
+            if to.1 > from.1 {
+                return Err(Error::InvalidPrice);
+            }
+            if rate_bump.is_none() && now < to.0 {
+                rate_bump = Some(interpolate(from, to, now));
+            }
+            from = to;
+        }
+        if rate_bump.is_none() && now < self.end_time {
+            rate_bump = Some(interpolate(from, (self.end_time, 0), now));
+        }
+
+        let rate_bump = u128::from(rate_bump.unwrap_or(0));
+        let denominator = u128::from(RATE_BUMP_DENOMINATOR);
+        mul_div(self.end_price, denominator + rate_bump, denominator).ok_or(Error::Overflow)
+    }
+
+    fn exponential_price(&self, half_life: u64, now: u64) -> Result<u128, Error> {
+        if self.end_time <= self.start_time || half_life == 0 {
+            return Err(Error::InvalidDuration);
+        }
+        if self.start_price < self.end_price {
+            return Err(Error::InvalidPrice);
+        }
+        if now >= self.end_time {
+            return Ok(self.end_price);
+        }
+
+        let time_elapsed = now - self.start_time;
+        let halvings = time_elapsed / half_life;
+        if halvings >= u128::BITS as u64 {
+            return Ok(self.end_price);
+        }
+
+        // Linear within each half-life, which keeps the integer curve monotonic
+        let gap = (self.start_price - self.end_price) >> halvings;
+        let within = mul_div(gap, u128::from(time_elapsed % half_life), 2 * u128::from(half_life))
+            .ok_or(Error::Overflow)?;
+        Ok(self.end_price + gap - within)
+    }
+}
+
+/// Rate bump at `now` on the segment between two `(time, rate_bump)` points, `now` before `to`
+fn interpolate(from: (u64, u32), to: (u64, u32), now: u64) -> u32 {
+    let decrease = u128::from(from.1 - to.1) * u128::from(now - from.0) / u128::from(to.0 - from.0);
+    from.1 - decrease as u32
+}
+
+/// `a * b / denominator` rounded down, with the product taken at 256 bits
+pub fn mul_div(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+
+    const LOW: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & LOW);
+    let (b_hi, b_lo) = (b >> 64, b & LOW);
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let middle = (lo_lo >> 64) + (hi_lo & LOW) + (lo_hi & LOW);
+    let lo = (lo_lo & LOW) | (middle << 64);
+    let hi = a_hi * b_hi + (hi_lo >> 64) + (lo_hi >> 64) + (middle >> 64);
+
+    // The quotient fits in 128 bits only if the high word is below the denominator
+    if hi >= denominator {
+        return None;
+    }
+
+    // Long division of the low word into the remainder left by the high one
+    let (mut quotient, mut remainder) = (0u128, hi);
+    for bit in (0..u128::BITS).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+    Some(quotient)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const P: u128 = PRICE_PRECISION;
+
+    fn auction(start_price: u128, end_price: u128, curve: Curve) -> Auction {
+        Auction { start_price, end_price, start_time: 1_000, end_time: 1_600, curve }
+    }
+
+    #[test]
+    fn mul_div_is_exact_past_128_bits() {
+        assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX), Some(u128::MAX));
+        assert_eq!(mul_div(u128::MAX, 3, 6), Some(u128::MAX / 2));
+        assert_eq!(mul_div(1 << 100, 1 << 100, 1 << 90), Some(1 << 110));
+        assert_eq!(mul_div(7, 5, 3), Some(11));
+        assert_eq!(mul_div(u128::MAX, 2, 1), None);
+        assert_eq!(mul_div(1, 1, 0), None);
+    }
+
+    #[test]
+    fn shared_vectors() {
+        for vector in vectors::linear().chain(vectors::curves()) {
+            let auction = vector.auction();
+            match vector.expected {
+                Ok((price, taking_amount)) => {
+                    assert_eq!(auction.price(vector.now), Ok(price), "{vector:?}");
+                    assert_eq!(auction.taking_amount(vector.making_amount, vector.now), Ok(taking_amount), "{vector:?}");
+                }
+                Err(error) => {
+                    assert_eq!(auction.taking_amount(vector.making_amount, vector.now), Err(error), "{vector:?}")
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn piecewise_curve_runs_through_points() {
+        let points = [Point { delay: 100, rate_bump: 500_000 }, Point { delay: 100, rate_bump: 400_000 }];
+        let auction = auction(0, P, Curve::PiecewiseLinear { initial_rate_bump: 1_000_000, points: &points });
+        assert_eq!(auction.price(1_000), Ok(1_100_000_000_000_000_000));
+        assert_eq!(auction.price(1_050), Ok(1_075_000_000_000_000_000));
+        assert_eq!(auction.price(1_200), Ok(1_040_000_000_000_000_000));
+        assert_eq!(auction.price(1_400), Ok(1_020_000_000_000_000_000));
+        assert_eq!(auction.price(1_600), Ok(P));
+    }
+
+    #[test]
+    fn exponential_curve_halves_the_gap() {
+        let auction = auction(2 * P, P, Curve::Exponential { half_life: 100 });
+        assert_eq!(auction.price(1_050), Ok(1_750_000_000_000_000_000));
+        assert_eq!(auction.price(1_100), Ok(1_500_000_000_000_000_000));
+        assert_eq!(auction.price(1_600), Ok(P));
+    }
+
+    #[test]
+    fn rejects_invalid_curves() {
+        let rising = [Point { delay: 10, rate_bump: 200 }];
+        let too_long = [Point { delay: 601, rate_bump: 0 }];
+        let cases = [
+            (auction(0, P, Curve::PiecewiseLinear { initial_rate_bump: 100, points: &rising }), Error::InvalidPrice),
+            (auction(0, P, Curve::PiecewiseLinear { initial_rate_bump: 100, points: &too_long }), Error::InvalidDuration),
+            (auction(2, 1, Curve::Exponential { half_life: 0 }), Error::InvalidDuration),
+            (auction(1, 2, Curve::Exponential { half_life: 10 }), Error::InvalidPrice),
+            (auction(u128::MAX, u128::MAX, Curve::PiecewiseLinear { initial_rate_bump: 1, points: &[] }), Error::Overflow),
+        ];
+        for (auction, error) in cases {
+            assert_eq!(auction.price(1_000), Err(error));
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Test vectors from `vectors.txt` and `curves.txt`, checked against each chain's resolver so
+//! they all quote the same prices
+
+use crate::{Auction, Curve, Error, Point};
+
+const LINEAR: &str = include_str!("../vectors.txt");
+const CURVES: &str = include_str!("../curves.txt");
+
+/// Most points a vector's piecewise-linear curve may have
+pub const MAX_POINTS: usize = 4;
+
+/// Curve of a vector, with the piecewise-linear points stored inline
+#[derive(Clone, Copy, Debug)]
+pub enum VectorCurve {
+    Linear,
+    PiecewiseLinear { initial_rate_bump: u32, points: [Point; MAX_POINTS], len: usize },
+    Exponential { half_life: u64 },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Vector {
+    pub start_price: u128,
+    pub end_price: u128,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub now: u64,
+    pub making_amount: u128,
+    pub curve: VectorCurve,
+    /// Price and taking amount at `now`, or the error quoting it
+    pub expected: Result<(u128, u128), Error>,
+}
+
+impl Vector {
+    pub fn auction(&self) -> Auction<'_> {
+        Auction {
+            start_price: self.start_price,
+            end_price: self.end_price,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            curve: self.curve(),
+        }
+    }
+
+    pub fn curve(&self) -> Curve<'_> {
+        match &self.curve {
+            VectorCurve::Linear => Curve::Linear,
+            VectorCurve::PiecewiseLinear { initial_rate_bump, points, len } => {
+                Curve::PiecewiseLinear { initial_rate_bump: *initial_rate_bump, points: &points[..*len] }
+            }
+            VectorCurve::Exponential { half_life } => Curve::Exponential { half_life: *half_life },
+        }
+    }
+}
+
+/// Linear auction vectors, the curve every chain supports
+pub fn linear() -> impl Iterator<Item = Vector> {
+    lines(LINEAR).map(|line| parse(VectorCurve::Linear, line))
+}
+
+/// Piecewise-linear and exponential auction vectors
+pub fn curves() -> impl Iterator<Item = Vector> {
+    lines(CURVES).map(|line| {
+        let (curve, rest) = line.split_once(' ').expect("missing vector curve");
+        parse(parse_curve(curve), rest)
+    })
+}
+
+fn lines(vectors: &str) -> impl Iterator<Item = &str> {
+    vectors.lines().filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+fn parse_curve(curve: &str) -> VectorCurve {
+    match curve.split_once(':').expect("missing curve parameters") {
+        ("exponential", half_life) => VectorCurve::Exponential { half_life: half_life.parse().unwrap() },
+        ("piecewise", params) => {
+            let mut params = params.split(',');
+            let initial_rate_bump = params.next().unwrap().parse().unwrap();
+            let mut points = [Point { delay: 0, rate_bump: 0 }; MAX_POINTS];
+            let mut len = 0;
+            for param in params {
+                let (delay, rate_bump) = param.split_once(':').expect("point is not delay:rate_bump");
+                points[len] = Point { delay: delay.parse().unwrap(), rate_bump: rate_bump.parse().unwrap() };
+                len += 1;
+            }
+            VectorCurve::PiecewiseLinear { initial_rate_bump, points, len }
+        }
+        (kind, _) => panic!("unknown curve {kind}"),
+    }
+}
+
+fn parse(curve: VectorCurve, line: &str) -> Vector {
+    let mut fields = line.split_whitespace();
+    let mut next = || fields.next().expect("missing vector field");
+    let (start_price, end_price) = (next().parse().unwrap(), next().parse().unwrap());
+    let (start_time, end_time, now) = (next().parse().unwrap(), next().parse().unwrap(), next().parse().unwrap());
+    let making_amount = next().parse().unwrap();
+    let expected = match next() {
+        "NotStarted" => Err(Error::NotStarted),
+        "InvalidDuration" => Err(Error::InvalidDuration),
+        "InvalidPrice" => Err(Error::InvalidPrice),
+        "Overflow" => Err(Error::Overflow),
+        price => Ok((price.parse().unwrap(), next().parse().unwrap())),
+    };
+    Vector { start_price, end_price, start_time, end_time, now, making_amount, curve, expected }
+}
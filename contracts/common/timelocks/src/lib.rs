@@ -0,0 +1,146 @@
+//! Escrow timelocks packed into one 256-bit word, bit for bit the EVM `TimelocksLib` layout:
+//! seven 32-bit stage offsets from the lowest bits up, and the deployment timestamp in the
+//! top 32 bits. Offsets are relative to the deployment timestamp.
+//!
+//! The word is handled as big-endian bytes so the crate stays `no_std` and dependency free;
+//! each chain converts to its own 256-bit integer type.
+
+#![no_std]
+
+/// Stages in the order they are packed, matching `TimelocksLib.Stage`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Stage {
+    SrcWithdrawal = 0,
+    SrcPublicWithdrawal = 1,
+    SrcCancellation = 2,
+    SrcPublicCancellation = 3,
+    DstWithdrawal = 4,
+    DstPublicWithdrawal = 5,
+    DstCancellation = 6,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 7] = [
+        Stage::SrcWithdrawal,
+        Stage::SrcPublicWithdrawal,
+        Stage::SrcCancellation,
+        Stage::SrcPublicCancellation,
+        Stage::DstWithdrawal,
+        Stage::DstPublicWithdrawal,
+        Stage::DstCancellation,
+    ];
+}
+
+const DEPLOYED_AT_SLOT: usize = 7;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Timelocks([u8; 32]);
+
+impl Timelocks {
+    /// Packs stage offsets, indexed by [`Stage`], and the deployment timestamp
+    pub fn encode(offsets: [u32; 7], deployed_at: u32) -> Self {
+        let mut timelocks = Timelocks([0u8; 32]);
+        for (slot, offset) in offsets.into_iter().enumerate() {
+            timelocks.set_slot(slot, offset);
+        }
+        timelocks.set_slot(DEPLOYED_AT_SLOT, deployed_at);
+        timelocks
+    }
+
+    /// Unpacks the stage offsets and the deployment timestamp
+    pub fn decode(&self) -> ([u32; 7], u32) {
+        (Stage::ALL.map(|stage| self.offset(stage)), self.deployed_at())
+    }
+
+    pub const fn from_be_bytes(bytes: [u8; 32]) -> Self {
+        Timelocks(bytes)
+    }
+
+    pub const fn to_be_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    pub fn offset(&self, stage: Stage) -> u32 {
+        self.slot(stage as usize)
+    }
+
+    pub fn deployed_at(&self) -> u32 {
+        self.slot(DEPLOYED_AT_SLOT)
+    }
+
+    /// Copy with the deployment timestamp replaced, as `TimelocksLib.setDeployedAt`
+    pub fn with_deployed_at(mut self, deployed_at: u32) -> Self {
+        self.set_slot(DEPLOYED_AT_SLOT, deployed_at);
+        self
+    }
+
+    /// Absolute timestamp at which `stage` starts
+    pub fn get(&self, stage: Stage) -> u64 {
+        self.deployed_at() as u64 + self.offset(stage) as u64
+    }
+
+    /// Absolute timestamp from which funds can be rescued
+    pub fn rescue_start(&self, rescue_delay: u32) -> u64 {
+        self.deployed_at() as u64 + rescue_delay as u64
+    }
+
+    fn slot(&self, slot: usize) -> u32 {
+        let end = 32 - 4 * slot;
+        u32::from_be_bytes([self.0[end - 4], self.0[end - 3], self.0[end - 2], self.0[end - 1]])
+    }
+
+    fn set_slot(&mut self, slot: usize, value: u32) {
+        let end = 32 - 4 * slot;
+        self.0[end - 4..end].copy_from_slice(&value.to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OFFSETS: [u32; 7] = [10, 120, 300, 400, 10, 100, 200];
+    const DEPLOYED_AT: u32 = 1_700_000_000;
+
+    // `TimelocksLib` word for OFFSETS deployed at DEPLOYED_AT
+    const PACKED: &str = "6553f100000000c8000000640000000a000001900000012c000000780000000a";
+
+    fn hex(bytes: [u8; 32]) -> [u8; 64] {
+        const DIGITS: &[u8; 16] = b"0123456789abcdef";
+        let mut out = [0u8; 64];
+        for (i, byte) in bytes.iter().enumerate() {
+            out[2 * i] = DIGITS[(byte >> 4) as usize];
+            out[2 * i + 1] = DIGITS[(byte & 0xf) as usize];
+        }
+        out
+    }
+
+    #[test]
+    fn encode_matches_evm_layout() {
+        let timelocks = Timelocks::encode(OFFSETS, DEPLOYED_AT);
+        assert_eq!(&hex(timelocks.to_be_bytes())[..], PACKED.as_bytes());
+    }
+
+    #[test]
+    fn decode_round_trips() {
+        let timelocks = Timelocks::encode(OFFSETS, DEPLOYED_AT);
+        assert_eq!(timelocks.decode(), (OFFSETS, DEPLOYED_AT));
+        assert_eq!(Timelocks::from_be_bytes(timelocks.to_be_bytes()), timelocks);
+
+        // Every slot is independent, including the top one
+        let extremes = [u32::MAX, 0, u32::MAX, 0, u32::MAX, 0, u32::MAX];
+        assert_eq!(Timelocks::encode(extremes, u32::MAX).decode(), (extremes, u32::MAX));
+    }
+
+    #[test]
+    fn stages_are_relative_to_deployment() {
+        let timelocks = Timelocks::encode(OFFSETS, 0).with_deployed_at(DEPLOYED_AT);
+        assert_eq!(timelocks.offset(Stage::DstCancellation), 200);
+        assert_eq!(timelocks.get(Stage::SrcCancellation), 1_700_000_300);
+        assert_eq!(timelocks.get(Stage::DstPublicWithdrawal), 1_700_000_100);
+        assert_eq!(timelocks.rescue_start(86_400), 1_700_086_400);
+        // Replacing the deployment time leaves the offsets alone
+        assert_eq!(timelocks.decode().0, OFFSETS);
+    }
+}
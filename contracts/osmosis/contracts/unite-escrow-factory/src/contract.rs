@@ -1,28 +1,297 @@
 use cosmwasm_std::{
-    to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    entry_point, instantiate2_address, to_json_binary, Addr, Binary, Coin, CosmosMsg, Deps,
+    DepsMut, Env, MessageInfo, Response, StdResult, Uint128, WasmMsg,
 };
+use cw2::set_contract_version;
+use sha2::{Digest, Sha256};
+use unite_escrow::msg::{EscrowInstantiateMsg, ExecuteMsg as EscrowExecuteMsg};
+use unite_escrow::types::{EscrowType, Immutables};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::msg::{
+    ConfigResponse, CreateEscrowResponse, EscrowAddressResponse, ExecuteMsg, InstantiateMsg,
+    QueryMsg,
+};
+use crate::state::{Config, CONFIG, DST_ESCROWS, ESCROW_IMMUTABLES, RESOLVERS, SRC_ESCROWS};
+
+const CONTRACT_NAME: &str = "crates.io:unite-escrow-factory";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+#[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    _deps: DepsMut,
+    deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
-    _msg: InstantiateMsg,
+    info: MessageInfo,
+    msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    Ok(Response::new().add_attribute("method", "instantiate"))
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let config = Config {
+        owner: info.sender,
+        escrow_code_id: msg.escrow_code_id,
+        order_protocol: msg.order_protocol,
+        safety_deposit_denom: msg.safety_deposit_denom,
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("escrow_code_id", config.escrow_code_id.to_string()))
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    _deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
-    _msg: ExecuteMsg,
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::CreateSrcEscrowPartialFor { immutables, partial_amount, resolver } => {
+            if partial_amount.is_zero() || partial_amount > immutables.amount {
+                return Err(ContractError::InvalidAmount {});
+            }
+            execute_create_escrow(deps, env, info, immutables, EscrowType::Source, None, partial_amount, resolver)
+        }
+        ExecuteMsg::CreateDstEscrowPartialFor {
+            immutables,
+            src_cancellation_timestamp,
+            partial_amount,
+            resolver,
+        } => {
+            // Destination amounts are in the destination token, so only a non-zero check applies
+            if partial_amount.is_zero() {
+                return Err(ContractError::InvalidAmount {});
+            }
+            execute_create_escrow(
+                deps,
+                env,
+                info,
+                immutables,
+                EscrowType::Destination,
+                Some(src_cancellation_timestamp),
+                partial_amount,
+                resolver,
+            )
+        }
+        ExecuteMsg::AddResolver { resolver } => execute_set_resolver(deps, info, resolver, true),
+        ExecuteMsg::RemoveResolver { resolver } => execute_set_resolver(deps, info, resolver, false),
+    }
+}
+
+pub fn execute_set_resolver(
+    deps: DepsMut,
+    info: MessageInfo,
+    resolver: Addr,
+    allowed: bool,
 ) -> Result<Response, ContractError> {
-    Ok(Response::new().add_attribute("method", "execute"))
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if allowed {
+        RESOLVERS.save(deps.storage, &resolver, &true)?;
+    } else {
+        RESOLVERS.remove(deps.storage, &resolver);
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", if allowed { "add_resolver" } else { "remove_resolver" })
+        .add_attribute("resolver", resolver))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_create_escrow(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    immutables: Immutables,
+    escrow_type: EscrowType,
+    src_cancellation_timestamp: Option<u64>,
+    partial_amount: Uint128,
+    resolver: Addr,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Escrows are only created through allow-listed resolver contracts
+    if !RESOLVERS.has(deps.storage, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let safety_deposit = info.funds.iter()
+        .find(|coin| coin.denom == config.safety_deposit_denom)
+        .map(|coin| coin.amount)
+        .unwrap_or(Uint128::zero());
+
+    if safety_deposit.is_zero() || safety_deposit < immutables.safety_deposit {
+        return Err(ContractError::InsufficientSafetyDeposit {});
+    }
+    let deposit = vec![Coin {
+        denom: config.safety_deposit_denom.clone(),
+        amount: safety_deposit,
+    }];
+
+    let escrows = match escrow_type {
+        EscrowType::Source => SRC_ESCROWS,
+        EscrowType::Destination => DST_ESCROWS,
+    };
+    let is_source = matches!(escrow_type, EscrowType::Source);
+
+    let immutables_hash = immutables.hash().to_vec();
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let escrow_address = match escrows.may_load(deps.storage, &immutables.order_hash)? {
+        Some(escrow_address) => {
+            // Subsequent resolver - only joins an escrow deployed for the same immutables
+            if ESCROW_IMMUTABLES.load(deps.storage, &escrow_address)? != immutables_hash {
+                return Err(ContractError::InvalidImmutables {});
+            }
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: escrow_address.to_string(),
+                msg: to_json_binary(&EscrowExecuteMsg::AddResolverSafetyDeposit {
+                    resolver: resolver.clone(),
+                    partial_amount,
+                })?,
+                funds: deposit,
+            }));
+            escrow_address
+        }
+        None => {
+            // First resolver - deploy the escrow at its predicted address
+            let salt = escrow_salt(&immutables, &escrow_type);
+            let escrow_address =
+                predict_escrow_address(deps.as_ref(), &env, config.escrow_code_id, &salt)?;
+
+            let init_msg = match escrow_type {
+                EscrowType::Source => EscrowExecuteMsg::Initialize {
+                    immutables: immutables.clone(),
+                    is_source: true,
+                },
+                EscrowType::Destination => EscrowExecuteMsg::InitializeDst {
+                    immutables: immutables.clone(),
+                    src_cancellation_timestamp: src_cancellation_timestamp.unwrap_or_default(),
+                },
+            };
+
+            messages.push(CosmosMsg::Wasm(WasmMsg::Instantiate2 {
+                admin: None,
+                code_id: config.escrow_code_id,
+                label: format!(
+                    "unite-escrow-{}-{}",
+                    if is_source { "src" } else { "dst" },
+                    immutables.order_hash
+                ),
+                msg: to_json_binary(&EscrowInstantiateMsg {
+                    immutables: immutables.clone(),
+                    escrow_type: escrow_type.clone(),
+                    src_cancellation_timestamp,
+                    safety_deposit_denom: config.safety_deposit_denom.clone(),
+                })?,
+                funds: vec![],
+                salt: Binary::from(salt),
+            }));
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: escrow_address.to_string(),
+                msg: to_json_binary(&init_msg)?,
+                funds: vec![],
+            }));
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: escrow_address.to_string(),
+                msg: to_json_binary(&EscrowExecuteMsg::HandleFirstResolver {
+                    resolver: resolver.clone(),
+                    partial_amount,
+                    resolver_deposit: safety_deposit,
+                })?,
+                funds: deposit,
+            }));
+
+            escrows.save(deps.storage, &immutables.order_hash, &escrow_address)?;
+            ESCROW_IMMUTABLES.save(deps.storage, &escrow_address, &immutables_hash)?;
+            escrow_address
+        }
+    };
+
+    let data = CreateEscrowResponse {
+        escrow_address: escrow_address.clone(),
+        order_hash: immutables.order_hash.clone(),
+        is_source,
+    };
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .set_data(to_json_binary(&data)?)
+        .add_attribute("method", if is_source { "create_src_escrow" } else { "create_dst_escrow" })
+        .add_attribute("escrow_address", escrow_address)
+        .add_attribute("order_hash", immutables.order_hash)
+        .add_attribute("resolver", resolver)
+        .add_attribute("partial_amount", partial_amount.to_string())
+        .add_attribute("safety_deposit", safety_deposit.to_string()))
+}
+
+/// Instantiate2 salt: sha256(order_hash || hashlock || "src" | "dst")
+pub fn escrow_salt(immutables: &Immutables, escrow_type: &EscrowType) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(immutables.order_hash.as_bytes());
+    hasher.update(immutables.hashlock.as_bytes());
+    hasher.update(match escrow_type {
+        EscrowType::Source => b"src",
+        EscrowType::Destination => b"dst",
+    });
+    hasher.finalize().to_vec()
+}
+
+fn predict_escrow_address(
+    deps: Deps,
+    env: &Env,
+    code_id: u64,
+    salt: &[u8],
+) -> Result<Addr, ContractError> {
+    let code_info = deps.querier.query_wasm_code_info(code_id)?;
+    let creator = deps.api.addr_canonicalize(env.contract.address.as_str())?;
+    let canonical = instantiate2_address(code_info.checksum.as_slice(), &creator, salt)?;
+    Ok(deps.api.addr_humanize(&canonical)?)
+}
+
+fn query_escrow_address(
+    deps: Deps,
+    env: Env,
+    immutables: Immutables,
+    escrow_type: EscrowType,
+) -> Result<EscrowAddressResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let salt = escrow_salt(&immutables, &escrow_type);
+    let escrow_address = predict_escrow_address(deps, &env, config.escrow_code_id, &salt)?;
+    Ok(EscrowAddressResponse { escrow_address })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    match msg {
+        QueryMsg::GetConfig {} => Ok(to_json_binary(&query_config(deps)?)?),
+        QueryMsg::AddressOfEscrowSrc { immutables } => Ok(to_json_binary(
+            &query_escrow_address(deps, env, immutables, EscrowType::Source)?,
+        )?),
+        QueryMsg::AddressOfEscrowDst { immutables } => Ok(to_json_binary(
+            &query_escrow_address(deps, env, immutables, EscrowType::Destination)?,
+        )?),
+        QueryMsg::GetSrcEscrow { order_hash } => Ok(to_json_binary(&EscrowAddressResponse {
+            escrow_address: SRC_ESCROWS.load(deps.storage, &order_hash)?,
+        })?),
+        QueryMsg::GetDstEscrow { order_hash } => Ok(to_json_binary(&EscrowAddressResponse {
+            escrow_address: DST_ESCROWS.load(deps.storage, &order_hash)?,
+        })?),
+        QueryMsg::IsResolver { resolver } => {
+            Ok(to_json_binary(&RESOLVERS.has(deps.storage, &resolver))?)
+        }
+    }
 }
 
-pub fn query(_deps: Deps, _env: Env, _msg: QueryMsg) -> StdResult<Binary> {
-    to_json_binary(&"query")
-}
\ No newline at end of file
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        owner: config.owner,
+        escrow_code_id: config.escrow_code_id,
+        order_protocol: config.order_protocol,
+        safety_deposit_denom: config.safety_deposit_denom,
+    })
+}
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Instantiate2AddressError, StdError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,6 +6,18 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Instantiate2Address(#[from] Instantiate2AddressError),
+
     #[error("Unauthorized")]
     Unauthorized {},
-}
\ No newline at end of file
+
+    #[error("Invalid amount")]
+    InvalidAmount {},
+
+    #[error("Immutables do not match the deployed escrow")]
+    InvalidImmutables {},
+
+    #[error("Insufficient safety deposit")]
+    InsufficientSafetyDeposit {},
+}
@@ -3,4 +3,7 @@ pub mod error;
 pub mod msg;
 pub mod state;
 
-pub use crate::error::ContractError;
\ No newline at end of file
+#[cfg(test)]
+mod tests;
+
+pub use crate::error::ContractError;
@@ -1,20 +1,71 @@
+use cosmwasm_std::{Addr, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use unite_escrow::types::Immutables;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+    pub escrow_code_id: u64,
+    pub order_protocol: Addr,
+    /// Bank denom in which resolver safety deposits are posted
+    pub safety_deposit_denom: String,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    CreateEscrow {
-        recipient: String,
-        amount: String,
-        timeout: u64,
+    /// Deploys the source escrow for `immutables` on first fill, otherwise adds
+    /// `resolver` to the existing one. The safety deposit is attached as funds.
+    CreateSrcEscrowPartialFor {
+        immutables: Immutables,
+        partial_amount: Uint128,
+        resolver: Addr,
+    },
+    /// Destination-side counterpart of `CreateSrcEscrowPartialFor`
+    CreateDstEscrowPartialFor {
+        immutables: Immutables,
+        src_cancellation_timestamp: u64,
+        partial_amount: Uint128,
+        resolver: Addr,
     },
+    /// Allows `resolver` to create escrows (owner only)
+    AddResolver { resolver: Addr },
+    /// Revokes `resolver` (owner only)
+    RemoveResolver { resolver: Addr },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum QueryMsg {}
\ No newline at end of file
+pub enum QueryMsg {
+    GetConfig {},
+    /// Predicted address of the source escrow for `immutables`, whether deployed or not
+    AddressOfEscrowSrc { immutables: Immutables },
+    /// Predicted address of the destination escrow for `immutables`, whether deployed or not
+    AddressOfEscrowDst { immutables: Immutables },
+    GetSrcEscrow { order_hash: String },
+    GetDstEscrow { order_hash: String },
+    /// Whether `resolver` may create escrows
+    IsResolver { resolver: Addr },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: Addr,
+    pub escrow_code_id: u64,
+    pub order_protocol: Addr,
+    pub safety_deposit_denom: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EscrowAddressResponse {
+    pub escrow_address: Addr,
+}
+
+/// Data set on the response of `CreateSrcEscrowPartialFor` / `CreateDstEscrowPartialFor`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreateEscrowResponse {
+    pub escrow_address: Addr,
+    pub order_hash: String,
+    pub is_source: bool,
+}
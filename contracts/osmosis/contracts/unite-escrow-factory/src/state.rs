@@ -1,11 +1,21 @@
 use cosmwasm_std::Addr;
-use cw_storage_plus::Item;
+use cw_storage_plus::{Item, Map};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub owner: Addr,
+    pub escrow_code_id: u64,
+    pub order_protocol: Addr,
+    pub safety_deposit_denom: String,
 }
 
-pub const CONFIG: Item<Config> = Item::new("config");
\ No newline at end of file
+pub const CONFIG: Item<Config> = Item::new("config");
+// order_hash => escrow address
+pub const SRC_ESCROWS: Map<&str, Addr> = Map::new("src_escrows");
+pub const DST_ESCROWS: Map<&str, Addr> = Map::new("dst_escrows");
+// escrow address => `Immutables::hash` it was deployed with
+pub const ESCROW_IMMUTABLES: Map<&Addr, Vec<u8>> = Map::new("escrow_immutables");
+// resolver contracts allowed to create escrows
+pub const RESOLVERS: Map<&Addr, bool> = Map::new("resolvers");
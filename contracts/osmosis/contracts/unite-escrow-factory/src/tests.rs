@@ -0,0 +1,406 @@
+use bech32::{FromBase32, ToBase32, Variant};
+use cosmwasm_std::testing::{MockApi, MockStorage};
+use cosmwasm_std::{
+    coins, from_json, instantiate2_address, Addr, Api, CanonicalAddr, Empty, RecoverPubkeyError,
+    StdError, StdResult, Storage, Uint128, VerificationError,
+};
+use cw_multi_test::{
+    AddressGenerator, App, AppBuilder, BankKeeper, Contract, ContractWrapper, Executor,
+    WasmKeeper,
+};
+use sha2::{Digest, Sha256};
+use unite_escrow::msg::{EscrowStateResponse, QueryMsg as EscrowQueryMsg};
+use unite_escrow::types::{AssetInfo, Immutables, Timelocks};
+
+use crate::msg::{
+    CreateEscrowResponse, EscrowAddressResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+};
+use crate::ContractError;
+
+const PREFIX: &str = "osmo";
+const DENOM: &str = "uosmo";
+const SAFETY_DEPOSIT: u128 = 100;
+
+/// MockApi with real bech32 addresses, so that instantiate2 addresses can be humanized
+struct OsmoApi(MockApi);
+
+impl OsmoApi {
+    fn new() -> Self {
+        OsmoApi(MockApi::default().with_prefix(PREFIX))
+    }
+
+    fn addr_make(&self, input: &str) -> Addr {
+        self.0.addr_make(input)
+    }
+}
+
+impl Api for OsmoApi {
+    fn addr_validate(&self, human: &str) -> StdResult<Addr> {
+        self.addr_canonicalize(human)?;
+        Ok(Addr::unchecked(human))
+    }
+
+    fn addr_canonicalize(&self, human: &str) -> StdResult<CanonicalAddr> {
+        match bech32::decode(human) {
+            Ok((prefix, data, Variant::Bech32)) if prefix == PREFIX => {
+                Ok(Vec::<u8>::from_base32(&data)
+                    .map_err(|e| StdError::generic_err(e.to_string()))?
+                    .into())
+            }
+            _ => Err(StdError::generic_err(format!("Invalid address: {human}"))),
+        }
+    }
+
+    fn addr_humanize(&self, canonical: &CanonicalAddr) -> StdResult<Addr> {
+        bech32::encode(PREFIX, canonical.as_slice().to_base32(), Variant::Bech32)
+            .map(Addr::unchecked)
+            .map_err(|e| StdError::generic_err(e.to_string()))
+    }
+
+    fn secp256k1_verify(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        self.0.secp256k1_verify(message_hash, signature, public_key)
+    }
+
+    fn secp256k1_recover_pubkey(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        recovery_param: u8,
+    ) -> Result<Vec<u8>, RecoverPubkeyError> {
+        self.0.secp256k1_recover_pubkey(message_hash, signature, recovery_param)
+    }
+
+    fn ed25519_verify(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        self.0.ed25519_verify(message, signature, public_key)
+    }
+
+    fn ed25519_batch_verify(
+        &self,
+        messages: &[&[u8]],
+        signatures: &[&[u8]],
+        public_keys: &[&[u8]],
+    ) -> Result<bool, VerificationError> {
+        self.0.ed25519_batch_verify(messages, signatures, public_keys)
+    }
+
+    fn debug(&self, message: &str) {
+        self.0.debug(message)
+    }
+}
+
+/// Generates chain-like addresses: instantiate2 follows the wasmd derivation
+struct Bech32Addresses;
+
+impl AddressGenerator for Bech32Addresses {
+    fn contract_address(
+        &self,
+        api: &dyn Api,
+        _storage: &mut dyn Storage,
+        code_id: u64,
+        instance_id: u64,
+    ) -> anyhow::Result<Addr> {
+        let digest = Sha256::digest(format!("contract/{code_id}/{instance_id}"));
+        Ok(api.addr_humanize(&digest.to_vec().into())?)
+    }
+
+    fn predictable_contract_address(
+        &self,
+        api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _code_id: u64,
+        _instance_id: u64,
+        checksum: &[u8],
+        creator: &CanonicalAddr,
+        salt: &[u8],
+    ) -> anyhow::Result<Addr> {
+        let canonical = instantiate2_address(checksum, creator, salt)?;
+        Ok(api.addr_humanize(&canonical)?)
+    }
+}
+
+fn factory_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    ))
+}
+
+fn escrow_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        unite_escrow::contract::execute,
+        unite_escrow::contract::instantiate,
+        unite_escrow::contract::query,
+    ))
+}
+
+struct Suite {
+    app: App<BankKeeper, OsmoApi, MockStorage>,
+    factory: Addr,
+    resolver_1: Addr,
+    resolver_2: Addr,
+    immutables: Immutables,
+}
+
+impl Suite {
+    fn new() -> Self {
+        let api = OsmoApi::new();
+        let owner = api.addr_make("owner");
+        let maker = api.addr_make("maker");
+        let resolver_1 = api.addr_make("resolver1");
+        let resolver_2 = api.addr_make("resolver2");
+        let order_protocol = api.addr_make("order_protocol");
+
+        let mut app = AppBuilder::new()
+            .with_api(api)
+            .with_wasm(WasmKeeper::new().with_address_generator(Bech32Addresses))
+            .build(|router, _, storage| {
+                for resolver in [&resolver_1, &resolver_2] {
+                    router
+                        .bank
+                        .init_balance(storage, resolver, coins(1_000, DENOM))
+                        .unwrap();
+                }
+            });
+
+        let escrow_code_id = app.store_code(escrow_contract());
+        let factory_code_id = app.store_code(factory_contract());
+        let factory = app
+            .instantiate_contract(
+                factory_code_id,
+                owner.clone(),
+                &InstantiateMsg {
+                    escrow_code_id,
+                    order_protocol,
+                    safety_deposit_denom: DENOM.to_string(),
+                },
+                &[],
+                "factory",
+                None,
+            )
+            .unwrap();
+        for resolver in [&resolver_1, &resolver_2] {
+            app.execute_contract(
+                owner.clone(),
+                factory.clone(),
+                &ExecuteMsg::AddResolver {
+                    resolver: resolver.clone(),
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        let immutables = Immutables {
+            order_hash: "order".to_string(),
+            hashlock: hex::encode(Sha256::digest([1u8; 32])),
+            maker: maker.clone(),
+            taker: maker,
+            token: AssetInfo::Native(DENOM.to_string()),
+            amount: Uint128::new(1000),
+            safety_deposit: Uint128::new(SAFETY_DEPOSIT),
+            timelocks: Timelocks::new([0, 1000, 2000, 3000, 0, 1000, 2000]),
+            parts_amount: None,
+        };
+
+        Suite {
+            app,
+            factory,
+            resolver_1,
+            resolver_2,
+            immutables,
+        }
+    }
+
+    fn create_src(
+        &mut self,
+        resolver: &Addr,
+        partial_amount: u128,
+        deposit: u128,
+    ) -> anyhow::Result<CreateEscrowResponse> {
+        let res = self.app.execute_contract(
+            resolver.clone(),
+            self.factory.clone(),
+            &ExecuteMsg::CreateSrcEscrowPartialFor {
+                immutables: self.immutables.clone(),
+                partial_amount: Uint128::new(partial_amount),
+                resolver: resolver.clone(),
+            },
+            &coins(deposit, DENOM),
+        )?;
+        Ok(from_json(res.data.unwrap())?)
+    }
+
+    fn predicted(&self, msg: QueryMsg) -> Addr {
+        let res: EscrowAddressResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(&self.factory, &msg)
+            .unwrap();
+        res.escrow_address
+    }
+}
+
+#[test]
+fn src_escrow_is_deployed_at_predicted_address() {
+    let mut suite = Suite::new();
+    let predicted = suite.predicted(QueryMsg::AddressOfEscrowSrc {
+        immutables: suite.immutables.clone(),
+    });
+
+    let resolver = suite.resolver_1.clone();
+    let created = suite.create_src(&resolver, 600, SAFETY_DEPOSIT).unwrap();
+    assert_eq!(created.escrow_address, predicted);
+    assert!(created.is_source);
+    assert_eq!(
+        suite.predicted(QueryMsg::GetSrcEscrow {
+            order_hash: "order".to_string()
+        }),
+        predicted
+    );
+
+    let state: EscrowStateResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&predicted, &EscrowQueryMsg::GetEscrowState {})
+        .unwrap();
+    assert!(state.is_source);
+    assert_eq!(state.factory, suite.factory);
+    assert_eq!(state.total_partial_amount, Uint128::new(600));
+    assert_eq!(
+        suite.app.wrap().query_balance(&predicted, DENOM).unwrap().amount,
+        Uint128::new(SAFETY_DEPOSIT)
+    );
+}
+
+#[test]
+fn later_resolvers_join_existing_escrow() {
+    let mut suite = Suite::new();
+    let (resolver_1, resolver_2) = (suite.resolver_1.clone(), suite.resolver_2.clone());
+
+    let first = suite.create_src(&resolver_1, 600, SAFETY_DEPOSIT).unwrap();
+    let second = suite.create_src(&resolver_2, 400, SAFETY_DEPOSIT).unwrap();
+    assert_eq!(first.escrow_address, second.escrow_address);
+
+    let count: u32 = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&first.escrow_address, &EscrowQueryMsg::GetResolverCount {})
+        .unwrap();
+    assert_eq!(count, 2);
+}
+
+#[test]
+fn src_and_dst_escrows_get_distinct_addresses() {
+    let mut suite = Suite::new();
+    let src = suite.predicted(QueryMsg::AddressOfEscrowSrc {
+        immutables: suite.immutables.clone(),
+    });
+    let dst = suite.predicted(QueryMsg::AddressOfEscrowDst {
+        immutables: suite.immutables.clone(),
+    });
+    assert_ne!(src, dst);
+
+    let resolver = suite.resolver_1.clone();
+    let res = suite
+        .app
+        .execute_contract(
+            resolver.clone(),
+            suite.factory.clone(),
+            &ExecuteMsg::CreateDstEscrowPartialFor {
+                immutables: suite.immutables.clone(),
+                src_cancellation_timestamp: 5000,
+                partial_amount: Uint128::new(1000),
+                resolver,
+            },
+            &coins(SAFETY_DEPOSIT, DENOM),
+        )
+        .unwrap();
+    let created: CreateEscrowResponse = from_json(res.data.unwrap()).unwrap();
+    assert_eq!(created.escrow_address, dst);
+    assert!(!created.is_source);
+}
+
+#[test]
+fn rejects_insufficient_safety_deposit() {
+    let mut suite = Suite::new();
+    let resolver = suite.resolver_1.clone();
+
+    let err = suite
+        .create_src(&resolver, 600, SAFETY_DEPOSIT - 1)
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::InsufficientSafetyDeposit {})
+    ));
+
+    let err = suite.create_src(&resolver, 1001, SAFETY_DEPOSIT).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::InvalidAmount {})
+    ));
+}
+
+#[test]
+fn only_allow_listed_resolvers_create_escrows() {
+    let mut suite = Suite::new();
+    let stranger = suite.app.api().addr_make("stranger");
+    suite
+        .app
+        .send_tokens(suite.resolver_1.clone(), stranger.clone(), &coins(SAFETY_DEPOSIT, DENOM))
+        .unwrap();
+
+    let err = suite.create_src(&stranger, 600, SAFETY_DEPOSIT).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::Unauthorized {})
+    ));
+
+    // Only the owner manages the allow-list
+    let err = suite
+        .app
+        .execute_contract(
+            stranger.clone(),
+            suite.factory.clone(),
+            &ExecuteMsg::AddResolver {
+                resolver: stranger.clone(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::Unauthorized {})
+    ));
+    let allowed: bool = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.factory, &QueryMsg::IsResolver { resolver: stranger })
+        .unwrap();
+    assert!(!allowed);
+}
+
+#[test]
+fn later_resolvers_must_present_the_deployed_immutables() {
+    let mut suite = Suite::new();
+    let (resolver_1, resolver_2) = (suite.resolver_1.clone(), suite.resolver_2.clone());
+    suite.create_src(&resolver_1, 600, SAFETY_DEPOSIT).unwrap();
+
+    // Same order hash, different hashlock
+    suite.immutables.hashlock = hex::encode(Sha256::digest([2u8; 32]));
+    let err = suite.create_src(&resolver_2, 400, SAFETY_DEPOSIT).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::InvalidImmutables {})
+    ));
+}
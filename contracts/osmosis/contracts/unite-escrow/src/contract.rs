@@ -1,14 +1,23 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    entry_point, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
-    Addr, Uint128, BankMsg, CosmosMsg, Coin, StdError,
+    from_json, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response,
+    StdResult, Addr, Uint128, CosmosMsg, StdError, Order,
 };
 use cw2::set_contract_version;
-use sha2::{Digest, Sha256};
+use cw20::Cw20ReceiveMsg;
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, QueryMsg, EscrowInstantiateMsg, EscrowStateResponse, ResolverInfoResponse};
-use crate::state::{ESCROW_STATE, RESOLVER_DEPOSITS, RESOLVERS, EscrowState, ResolverDeposit};
-use crate::types::{Immutables, State, EscrowType};
+use crate::msg::{
+    ExecuteMsg, QueryMsg, EscrowInstantiateMsg, EscrowStateResponse, ResolverInfoResponse,
+    Cw20HookMsg, ConfigResponse,
+};
+use crate::state::{
+    CONFIG, ESCROW_STATE, RESOLVER_DEPOSITS, RESOLVERS, USED_SECRET_INDICES, Config, EscrowState,
+    ResolverDeposit,
+};
+use crate::merkle;
+use crate::types::{AssetInfo, Immutables, State, EscrowType, Stage};
 
 const CONTRACT_NAME: &str = "crates.io:unite-escrow";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -19,10 +28,18 @@ pub fn instantiate(
     deps: DepsMut,
     _env: Env,
     _info: MessageInfo,
-    _msg: EscrowInstantiateMsg,
+    msg: EscrowInstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    Ok(Response::new().add_attribute("method", "instantiate"))
+    
+    let config = Config {
+        safety_deposit_denom: msg.safety_deposit_denom,
+    };
+    CONFIG.save(deps.storage, &config)?;
+    
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("safety_deposit_denom", config.safety_deposit_denom))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -42,8 +59,14 @@ pub fn execute(
         ExecuteMsg::AddResolverSafetyDeposit { resolver, partial_amount } => {
             execute_add_resolver_safety_deposit(deps, env, info, resolver, partial_amount)
         }
-        ExecuteMsg::WithdrawWithSecret { secret, immutables } => {
-            execute_withdraw_with_secret(deps, env, info, secret, immutables)
+        ExecuteMsg::RevealSecret { secret, immutables, index, proof } => {
+            execute_reveal_secret(deps, env, info, secret, immutables, index, proof)
+        }
+        ExecuteMsg::WithdrawWithSecret { secret, immutables, index, proof } => {
+            execute_withdraw_with_secret(deps, env, info, secret, immutables, index, proof)
+        }
+        ExecuteMsg::ClaimResolverShare { immutables } => {
+            execute_claim_resolver_share(deps, env, info, immutables)
         }
         ExecuteMsg::Cancel { immutables } => {
             execute_cancel(deps, env, info, immutables)
@@ -54,6 +77,12 @@ pub fn execute(
         ExecuteMsg::MarkUserFunded {} => {
             execute_mark_user_funded(deps, env, info)
         }
+        ExecuteMsg::Fund { immutables } => {
+            execute_fund(deps, env, info, immutables)
+        }
+        ExecuteMsg::Receive(cw20_msg) => {
+            execute_receive(deps, env, info, cw20_msg)
+        }
     }
 }
 
@@ -73,14 +102,17 @@ pub fn execute_initialize(
     immutables.timelocks.set_deployed_at(env.block.time.seconds());
     
     let state = EscrowState {
-        immutables: immutables.clone(),
+        immutables_hash: hex::encode(immutables.hash()),
         escrow_type: if is_source { EscrowType::Source } else { EscrowType::Destination },
         src_cancellation_timestamp: None,
         state: State::Active,
         deployed_at: env.block.time.seconds(),
+        amount: immutables.amount,
+        parts_amount: immutables.parts_amount,
         total_partial_amount: Uint128::zero(),
         total_partial_withdrawn: Uint128::zero(),
         funds_distributed: false,
+        funded_amount: Uint128::zero(),
         user_funded: false,
         factory: info.sender,
     };
@@ -110,14 +142,17 @@ pub fn execute_initialize_dst(
     immutables.timelocks.set_deployed_at(env.block.time.seconds());
     
     let state = EscrowState {
-        immutables: immutables.clone(),
+        immutables_hash: hex::encode(immutables.hash()),
         escrow_type: EscrowType::Destination,
         src_cancellation_timestamp: Some(src_cancellation_timestamp),
         state: State::Active,
         deployed_at: env.block.time.seconds(),
+        amount: immutables.amount,
+        parts_amount: immutables.parts_amount,
         total_partial_amount: Uint128::zero(),
         total_partial_withdrawn: Uint128::zero(),
         funds_distributed: false,
+        funded_amount: Uint128::zero(),
         user_funded: false,
         factory: info.sender,
     };
@@ -139,8 +174,13 @@ pub fn execute_add_resolver_safety_deposit(
     partial_amount: Uint128,
 ) -> Result<Response, ContractError> {
     // Check if initialized
-    if ESCROW_STATE.may_load(deps.storage)?.is_none() {
-        return Err(ContractError::NotInitialized {});
+    let mut state = ESCROW_STATE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NotInitialized {})?;
+    
+    // Resolvers join through the factory, which checks the caller and the immutables
+    if info.sender != state.factory {
+        return Err(ContractError::Unauthorized {});
     }
     
     // Check if resolver already exists
@@ -148,13 +188,12 @@ pub fn execute_add_resolver_safety_deposit(
         return Err(ContractError::ResolverAlreadyExists {});
     }
     
-    if partial_amount.is_zero() {
-        return Err(ContractError::InvalidPartialAmount {});
-    }
+    check_can_join(deps.as_ref(), &state, partial_amount)?;
     
     // Check safety deposit
+    let config = CONFIG.load(deps.storage)?;
     let safety_deposit = info.funds.iter()
-        .find(|coin| coin.denom == "uosmo")
+        .find(|coin| coin.denom == config.safety_deposit_denom)
         .map(|coin| coin.amount)
         .unwrap_or(Uint128::zero());
     
@@ -167,6 +206,7 @@ pub fn execute_add_resolver_safety_deposit(
         partial_amount,
         safety_deposit,
         withdrawn: false,
+        funded: Uint128::zero(),
     };
     RESOLVER_DEPOSITS.save(deps.storage, &resolver, &deposit)?;
     
@@ -176,7 +216,6 @@ pub fn execute_add_resolver_safety_deposit(
     RESOLVERS.save(deps.storage, &resolvers)?;
     
     // Update total partial amount
-    let mut state = ESCROW_STATE.load(deps.storage)?;
     state.total_partial_amount = state.total_partial_amount.checked_add(partial_amount)?;
     ESCROW_STATE.save(deps.storage, &state)?;
     
@@ -187,62 +226,49 @@ pub fn execute_add_resolver_safety_deposit(
         .add_attribute("safety_deposit", safety_deposit.to_string()))
 }
 
+pub fn execute_reveal_secret(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    secret: String,
+    immutables: Immutables,
+    index: Option<u16>,
+    proof: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    let state = ESCROW_STATE.load(deps.storage)?;
+    verify_immutables(&state, &immutables)?;
+    check_withdrawal_stage(deps.as_ref(), &env, &info, &state, &immutables)?;
+    let (index, resolvers) = reveal_secret(deps.branch(), &state, &immutables, &secret, index, proof)?;
+    
+    Ok(Response::new()
+        .add_attribute("method", "reveal_secret")
+        .add_attribute("caller", info.sender.to_string())
+        .add_attribute("secret_index", index.to_string())
+        .add_attribute("unlocked_resolvers", resolvers.len().to_string()))
+}
+
+/// Reveals the secret and pays out every fill it unlocks in one go
 pub fn execute_withdraw_with_secret(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     secret: String,
     immutables: Immutables,
+    index: Option<u16>,
+    proof: Option<Vec<String>>,
 ) -> Result<Response, ContractError> {
     let mut state = ESCROW_STATE.load(deps.storage)?;
-    
-    // Check if active
-    if state.state != State::Active {
-        return Err(ContractError::InvalidState {});
-    }
-    
-    // Verify secret
-    let secret_bytes = hex::decode(&secret).map_err(|_| ContractError::InvalidSecret {})?;
-    let mut hasher = Sha256::new();
-    hasher.update(&secret_bytes);
-    let computed_hash = hex::encode(hasher.finalize());
-    
-    if computed_hash != state.immutables.hashlock {
-        return Err(ContractError::InvalidSecret {});
-    }
-    
-    // Verify immutables match
-    verify_immutables(&state.immutables, &immutables)?;
-    
-    // Prevent double withdrawal
-    if state.funds_distributed {
-        return Err(ContractError::AlreadyWithdrawn {});
-    }
+    verify_immutables(&state, &immutables)?;
+    let stage = check_withdrawal_stage(deps.as_ref(), &env, &info, &state, &immutables)?;
+    let (index, resolvers) = reveal_secret(deps.branch(), &state, &immutables, &secret, index, proof)?;
     
     // For destination chain, check that all resolvers have deposited their promised tokens
-    if matches!(state.escrow_type, EscrowType::Destination) {
-        // In Osmosis, we need to check the contract's token balance
-        // This would require querying the token contract balance
-        // For now, we'll assume tokens are deposited as part of the transaction
-    }
-    
-    // Check if caller should get reward
-    let current_time = env.block.time.seconds();
-    let deployed_at = state.immutables.timelocks.get_deployed_at();
-    
-    let is_after_time_limit = match state.escrow_type {
-        EscrowType::Source => {
-            current_time >= deployed_at + state.immutables.timelocks.src_public_withdrawal
-        }
-        EscrowType::Destination => {
-            current_time >= deployed_at + state.immutables.timelocks.dst_public_withdrawal
-        }
-    };
+    ensure_destination_funded(deps.as_ref(), &env, &state, &immutables)?;
     
     // Check if caller is eligible for reward
-    let resolvers = RESOLVERS.load(deps.storage)?;
-    let is_resolver = resolvers.iter().any(|r| r == &info.sender);
-    let caller_gets_reward = is_after_time_limit && info.sender != state.immutables.maker && !is_resolver;
+    let is_after_time_limit = stage == Stage::PublicWithdrawal;
+    let is_resolver = RESOLVER_DEPOSITS.has(deps.storage, &info.sender);
+    let caller_gets_reward = is_after_time_limit && info.sender != immutables.maker && !is_resolver;
     
     // Calculate caller reward if applicable
     let mut caller_reward = Uint128::zero();
@@ -251,44 +277,87 @@ pub fn execute_withdraw_with_secret(
         caller_reward = total_safety_deposits.multiply_ratio(CALLER_REWARD_PERCENTAGE, 100u128);
     }
     
-    state.funds_distributed = true;
-    ESCROW_STATE.save(deps.storage, &state)?;
-    
     // Prepare messages for fund distribution
     let mut messages = vec![];
     
     match state.escrow_type {
         EscrowType::Source => {
-            messages.extend(distribute_source_funds(deps.as_ref(), &state, &resolvers, caller_reward)?);
+            messages.extend(distribute_source_funds(deps.as_ref(), &immutables, &resolvers, caller_reward)?);
         }
         EscrowType::Destination => {
-            messages.extend(distribute_destination_funds(deps.as_ref(), &state, &resolvers, caller_reward)?);
+            messages.extend(distribute_destination_funds(deps.as_ref(), &immutables, &resolvers, caller_reward)?);
         }
     }
     
     // Send caller reward if applicable
     if !caller_reward.is_zero() {
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: info.sender.to_string(),
-            amount: vec![Coin {
-                denom: "uosmo".to_string(),
-                amount: caller_reward,
-            }],
-        }));
+        let config = CONFIG.load(deps.storage)?;
+        let deposit_asset = AssetInfo::Native(config.safety_deposit_denom);
+        messages.push(deposit_asset.transfer_msg(&info.sender, caller_reward)?);
     }
     
-    // Update state to withdrawn
-    state.state = State::Withdrawn;
-    ESCROW_STATE.save(deps.storage, &state)?;
+    mark_withdrawn(deps, &mut state, &resolvers)?;
     
     Ok(Response::new()
         .add_messages(messages)
         .add_attribute("method", "withdraw_with_secret")
         .add_attribute("caller", info.sender.to_string())
+        .add_attribute("secret_index", index.to_string())
         .add_attribute("is_after_time_limit", is_after_time_limit.to_string())
         .add_attribute("caller_reward", caller_reward.to_string()))
 }
 
+/// Pays out the sender's fill once the secret covering it has been revealed
+pub fn execute_claim_resolver_share(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    immutables: Immutables,
+) -> Result<Response, ContractError> {
+    let mut state = ESCROW_STATE.load(deps.storage)?;
+    
+    // Check if active
+    if state.state != State::Active {
+        return Err(ContractError::InvalidState {});
+    }
+    
+    // Verify immutables match
+    verify_immutables(&state, &immutables)?;
+    
+    let deposit = RESOLVER_DEPOSITS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::Unauthorized {})?;
+    if deposit.withdrawn {
+        return Err(ContractError::AlreadyWithdrawn {});
+    }
+    
+    let index = resolver_secret_index(deps.as_ref(), &immutables, &info.sender)?;
+    if !USED_SECRET_INDICES.has(deps.storage, index) {
+        return Err(ContractError::SecretNotRevealed {});
+    }
+    
+    ensure_destination_funded(deps.as_ref(), &env, &state, &immutables)?;
+    
+    let resolvers = vec![info.sender.clone()];
+    let messages = match state.escrow_type {
+        EscrowType::Source => {
+            distribute_source_funds(deps.as_ref(), &immutables, &resolvers, Uint128::zero())?
+        }
+        EscrowType::Destination => {
+            distribute_destination_funds(deps.as_ref(), &immutables, &resolvers, Uint128::zero())?
+        }
+    };
+    
+    mark_withdrawn(deps, &mut state, &resolvers)?;
+    
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("method", "claim_resolver_share")
+        .add_attribute("resolver", info.sender.to_string())
+        .add_attribute("secret_index", index.to_string())
+        .add_attribute("partial_amount", deposit.partial_amount.to_string()))
+}
+
 pub fn execute_cancel(
     deps: DepsMut,
     env: Env,
@@ -303,71 +372,101 @@ pub fn execute_cancel(
     }
     
     // Verify immutables match
-    verify_immutables(&state.immutables, &immutables)?;
+    verify_immutables(&state, &immutables)?;
     
     // Check cancellation time windows
-    let deployed_at = state.immutables.timelocks.get_deployed_at();
     let current_time = env.block.time.seconds();
+    match immutables.timelocks.stage(&state.escrow_type, current_time) {
+        Stage::FinalityLock | Stage::PrivateWithdrawal | Stage::PublicWithdrawal => {
+            return Err(ContractError::CancellationNotStarted {});
+        }
+        Stage::PrivateCancellation => {
+            let is_maker = info.sender == immutables.maker
+                && matches!(state.escrow_type, EscrowType::Source);
+            if !is_maker && !is_taker(deps.as_ref(), &immutables, &info.sender) {
+                return Err(ContractError::PrivateCancellationOnly {});
+            }
+        }
+        Stage::PublicCancellation => {}
+    }
+    
+    // The destination side must not be cancelled before the source side can be
+    if matches!(state.escrow_type, EscrowType::Destination) {
+        match state.src_cancellation_timestamp {
+            Some(src_cancel_time) if current_time >= src_cancel_time => {}
+            _ => return Err(ContractError::CancellationNotStarted {}),
+        }
+    }
+    
+    state.state = State::Cancelled;
+    ESCROW_STATE.save(deps.storage, &state)?;
+    
+    // Resolvers that have not been paid out yet
+    let mut pending = vec![];
+    for resolver in RESOLVERS.load(deps.storage)? {
+        let deposit = RESOLVER_DEPOSITS.load(deps.storage, &resolver)?;
+        if !deposit.withdrawn {
+            pending.push((resolver, deposit));
+        }
+    }
+    
+    let config = CONFIG.load(deps.storage)?;
+    let deposit_asset = AssetInfo::Native(config.safety_deposit_denom);
     
+    // Escrowed asset still held, excluding safety deposits paid in the same denom
+    let mut locked = immutables.token.query_balance(&deps.querier, &env.contract.address)?;
+    if immutables.token == deposit_asset {
+        for (_, deposit) in &pending {
+            locked = locked.saturating_sub(deposit.safety_deposit);
+        }
+    }
+    let refunded = locked;
+    
+    let mut messages = vec![];
     match state.escrow_type {
+        // Fills whose secret was revealed belong to their resolvers, the rest of the
+        // maker's deposit goes back to the maker
         EscrowType::Source => {
-            let cancellation_time = deployed_at + state.immutables.timelocks.src_cancellation;
-            let public_cancellation_time = deployed_at + state.immutables.timelocks.src_public_cancellation;
-            
-            if current_time < cancellation_time {
-                return Err(ContractError::InvalidTime {});
+            for (resolver, deposit) in &pending {
+                let index = resolver_secret_index(deps.as_ref(), &immutables, resolver)?;
+                if !USED_SECRET_INDICES.has(deps.storage, index) {
+                    continue;
+                }
+                let share = deposit.partial_amount.min(locked);
+                locked -= share;
+                if !share.is_zero() {
+                    messages.push(immutables.token.transfer_msg(resolver, share)?);
+                }
             }
-            if current_time < public_cancellation_time && info.sender != state.immutables.maker {
-                return Err(ContractError::InvalidCaller {});
+            if !locked.is_zero() {
+                messages.push(immutables.token.transfer_msg(&immutables.maker, locked)?);
             }
         }
+        // Each resolver gets back exactly what it deposited
         EscrowType::Destination => {
-            if let Some(src_cancel_time) = state.src_cancellation_timestamp {
-                if current_time < src_cancel_time {
-                    return Err(ContractError::InvalidTime {});
+            for (resolver, deposit) in &pending {
+                let refund = deposit.funded.min(locked);
+                locked -= refund;
+                if !refund.is_zero() {
+                    messages.push(immutables.token.transfer_msg(resolver, refund)?);
                 }
-            } else {
-                return Err(ContractError::InvalidTime {});
-            }
-            
-            let cancellation_time = deployed_at + state.immutables.timelocks.dst_cancellation;
-            if current_time < cancellation_time {
-                return Err(ContractError::InvalidTime {});
             }
         }
     }
     
-    state.state = State::Cancelled;
-    ESCROW_STATE.save(deps.storage, &state)?;
-    
-    // Return all funds
-    let mut messages = vec![];
-    
-    // Return tokens to maker (if any in contract)
-    // In Osmosis, we would need to handle token transfers differently
-    // For native tokens (uosmo), we can use BankMsg
-    
     // Return safety deposits to resolvers
-    let resolvers = RESOLVERS.load(deps.storage)?;
-    for resolver in resolvers {
-        if let Some(deposit) = RESOLVER_DEPOSITS.may_load(deps.storage, &resolver)? {
-            if !deposit.safety_deposit.is_zero() {
-                messages.push(CosmosMsg::Bank(BankMsg::Send {
-                    to_address: resolver.to_string(),
-                    amount: vec![Coin {
-                        denom: "uosmo".to_string(),
-                        amount: deposit.safety_deposit,
-                    }],
-                }));
-            }
+    for (resolver, deposit) in &pending {
+        if !deposit.safety_deposit.is_zero() {
+            messages.push(deposit_asset.transfer_msg(resolver, deposit.safety_deposit)?);
         }
     }
     
     Ok(Response::new()
         .add_messages(messages)
         .add_attribute("method", "cancel")
-        .add_attribute("maker", state.immutables.maker.to_string())
-        .add_attribute("amount", state.immutables.amount.to_string()))
+        .add_attribute("maker", immutables.maker.to_string())
+        .add_attribute("amount", immutables.amount.to_string())
+        .add_attribute("refunded", refunded.to_string()))
 }
 
 pub fn execute_handle_first_resolver(
@@ -395,15 +494,14 @@ pub fn execute_handle_first_resolver(
         return Err(ContractError::ResolverAlreadyExists {});
     }
     
-    if partial_amount.is_zero() {
-        return Err(ContractError::InvalidPartialAmount {});
-    }
+    check_can_join(deps.as_ref(), &state, partial_amount)?;
     
     // Save resolver deposit
     let deposit = ResolverDeposit {
         partial_amount,
         safety_deposit: resolver_deposit,
         withdrawn: false,
+        funded: Uint128::zero(),
     };
     RESOLVER_DEPOSITS.save(deps.storage, &resolver, &deposit)?;
     
@@ -441,20 +539,334 @@ pub fn execute_mark_user_funded(
         .add_attribute("method", "mark_user_funded"))
 }
 
+pub fn execute_fund(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    immutables: Immutables,
+) -> Result<Response, ContractError> {
+    let mut state = ESCROW_STATE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NotInitialized {})?;
+    
+    if state.state != State::Active {
+        return Err(ContractError::InvalidState {});
+    }
+    
+    verify_immutables(&state, &immutables)?;
+    
+    let denom = match &immutables.token {
+        AssetInfo::Native(denom) => denom,
+        AssetInfo::Cw20(_) => return Err(ContractError::InvalidToken {}),
+    };
+    let amount = info.funds.iter()
+        .find(|coin| coin.denom == *denom)
+        .map(|coin| coin.amount)
+        .unwrap_or(Uint128::zero());
+    if amount.is_zero() {
+        return Err(ContractError::InvalidAmount {});
+    }
+    
+    record_funding(deps, &mut state, &immutables, &info.sender, amount)?;
+    
+    Ok(Response::new()
+        .add_attribute("method", "fund")
+        .add_attribute("sender", info.sender)
+        .add_attribute("amount", amount))
+}
+
+pub fn execute_receive(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let mut state = ESCROW_STATE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NotInitialized {})?;
+    
+    if state.state != State::Active {
+        return Err(ContractError::InvalidState {});
+    }
+    
+    if cw20_msg.amount.is_zero() {
+        return Err(ContractError::InvalidAmount {});
+    }
+    
+    match from_json(&cw20_msg.msg)? {
+        Cw20HookMsg::Fund { immutables } => {
+            verify_immutables(&state, &immutables)?;
+            
+            // Only the escrowed CW20 token may call the hook
+            match &immutables.token {
+                AssetInfo::Cw20(contract_addr) if *contract_addr == info.sender => {}
+                _ => return Err(ContractError::InvalidToken {}),
+            }
+            
+            let sender = deps.api.addr_validate(&cw20_msg.sender)?;
+            record_funding(deps, &mut state, &immutables, &sender, cw20_msg.amount)?;
+            
+            Ok(Response::new()
+                .add_attribute("method", "receive")
+                .add_attribute("sender", cw20_msg.sender)
+                .add_attribute("token", info.sender)
+                .add_attribute("amount", cw20_msg.amount))
+        }
+    }
+}
+
 // Helper functions
-fn verify_immutables(stored: &Immutables, provided: &Immutables) -> Result<(), ContractError> {
-    if stored.order_hash != provided.order_hash ||
-       stored.hashlock != provided.hashlock ||
-       stored.maker != provided.maker ||
-       stored.taker != provided.taker ||
-       stored.token != provided.token ||
-       stored.amount != provided.amount ||
-       stored.safety_deposit != provided.safety_deposit {
+/// Records escrowed tokens received from `sender`: the maker's deposit on a source escrow,
+/// which funds it once it covers the order, or a resolver's fill on a destination escrow
+fn record_funding(
+    deps: DepsMut,
+    state: &mut EscrowState,
+    immutables: &Immutables,
+    sender: &Addr,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    match state.escrow_type {
+        EscrowType::Source => {
+            state.funded_amount = state.funded_amount.checked_add(amount)?;
+            if state.funded_amount >= immutables.amount {
+                state.user_funded = true;
+            }
+            ESCROW_STATE.save(deps.storage, state)?;
+        }
+        EscrowType::Destination => {
+            // Tracked per resolver so a cancellation refunds exactly what each one put in
+            let mut deposit = RESOLVER_DEPOSITS
+                .may_load(deps.storage, sender)?
+                .ok_or(ContractError::Unauthorized {})?;
+            deposit.funded = deposit.funded.checked_add(amount)?;
+            RESOLVER_DEPOSITS.save(deps.storage, sender, &deposit)?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks `provided` against the immutables hash recorded at initialization
+fn verify_immutables(state: &EscrowState, provided: &Immutables) -> Result<(), ContractError> {
+    if hex::encode(provided.hash()) != state.immutables_hash {
         return Err(ContractError::InvalidImmutables {});
     }
     Ok(())
 }
 
+fn parse_hash(node: &str) -> Result<merkle::Hash, ContractError> {
+    hex::decode(node)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(ContractError::InvalidMerkleProof {})
+}
+
+/// Enforces the withdrawal stages and returns the current one
+fn check_withdrawal_stage(
+    deps: Deps,
+    env: &Env,
+    info: &MessageInfo,
+    state: &EscrowState,
+    immutables: &Immutables,
+) -> Result<Stage, ContractError> {
+    let stage = immutables.timelocks.stage(&state.escrow_type, env.block.time.seconds());
+    match stage {
+        Stage::FinalityLock => Err(ContractError::FinalityLock {}),
+        Stage::PrivateWithdrawal if !is_taker(deps, immutables, &info.sender) => {
+            Err(ContractError::PrivateWithdrawalOnly {})
+        }
+        Stage::PrivateCancellation | Stage::PublicCancellation => {
+            Err(ContractError::WithdrawalPeriodEnded {})
+        }
+        _ => Ok(stage),
+    }
+}
+
+/// Resolvers may join only while the escrow is active, up to the escrowed amount, and while
+/// the secret for their cumulative fill is still unrevealed
+fn check_can_join(deps: Deps, state: &EscrowState, partial_amount: Uint128) -> Result<(), ContractError> {
+    if state.state != State::Active {
+        return Err(ContractError::InvalidState {});
+    }
+    
+    let filled = state.total_partial_amount.checked_add(partial_amount)?;
+    if partial_amount.is_zero() || filled > state.amount {
+        return Err(ContractError::InvalidPartialAmount {});
+    }
+    
+    let index = match state.parts_amount {
+        None => 0,
+        Some(parts_amount) => merkle::secret_index(filled, state.amount, parts_amount),
+    };
+    if USED_SECRET_INDICES.has(deps.storage, index) {
+        return Err(ContractError::SecretAlreadyRevealed {});
+    }
+    Ok(())
+}
+
+/// The order taker or one of the resolvers filling it
+fn is_taker(deps: Deps, immutables: &Immutables, address: &Addr) -> bool {
+    *address == immutables.taker || RESOLVER_DEPOSITS.has(deps.storage, address)
+}
+
+/// Checks `secret` against the hashlock and records its index as revealed. A single-secret
+/// escrow uses index 0. Returns the index and the unpaid resolvers it unlocks.
+fn reveal_secret(
+    deps: DepsMut,
+    state: &EscrowState,
+    immutables: &Immutables,
+    secret: &str,
+    index: Option<u16>,
+    proof: Option<Vec<String>>,
+) -> Result<(u16, Vec<Addr>), ContractError> {
+    // Check if active
+    if state.state != State::Active {
+        return Err(ContractError::InvalidState {});
+    }
+    
+    // Prevent double withdrawal
+    if state.funds_distributed {
+        return Err(ContractError::AlreadyWithdrawn {});
+    }
+    
+    let secret_bytes = hex::decode(secret).map_err(|_| ContractError::InvalidSecret {})?;
+    let secret_hash = merkle::sha256(&secret_bytes);
+    
+    let index = match immutables.parts_amount {
+        None => {
+            if hex::encode(secret_hash) != immutables.hashlock {
+                return Err(ContractError::InvalidSecret {});
+            }
+            0
+        }
+        Some(parts_amount) => {
+            let index = index.ok_or(ContractError::InvalidSecretIndex {})?;
+            if index > parts_amount {
+                return Err(ContractError::InvalidSecretIndex {});
+            }
+            
+            let proof = proof
+                .unwrap_or_default()
+                .iter()
+                .map(|node| parse_hash(node))
+                .collect::<Result<Vec<_>, _>>()?;
+            let root = merkle::process_proof(merkle::leaf(index, &secret_hash), &proof);
+            if hex::encode(root) != immutables.hashlock {
+                return Err(ContractError::InvalidMerkleProof {});
+            }
+            index
+        }
+    };
+    
+    if USED_SECRET_INDICES.has(deps.storage, index) {
+        return Err(ContractError::SecretIndexUsed {});
+    }
+    USED_SECRET_INDICES.save(deps.storage, index, &true)?;
+    
+    let resolvers = resolvers_for_secret(deps.as_ref(), immutables, index)?;
+    if resolvers.is_empty() {
+        return Err(ContractError::InvalidSecretIndex {});
+    }
+    Ok((index, resolvers))
+}
+
+/// Resolvers not yet paid whose fill maps to the secret at `index`
+fn resolvers_for_secret(deps: Deps, immutables: &Immutables, index: u16) -> StdResult<Vec<Addr>> {
+    let mut filled = Uint128::zero();
+    let mut matched = vec![];
+    for resolver in RESOLVERS.load(deps.storage)? {
+        let deposit = RESOLVER_DEPOSITS.load(deps.storage, &resolver)?;
+        filled = filled.checked_add(deposit.partial_amount)?;
+        if secret_index_for_fill(immutables, filled) == index && !deposit.withdrawn {
+            matched.push(resolver);
+        }
+    }
+    Ok(matched)
+}
+
+/// Index of the secret unlocking `resolver`'s fill
+fn resolver_secret_index(deps: Deps, immutables: &Immutables, resolver: &Addr) -> StdResult<u16> {
+    let mut filled = Uint128::zero();
+    for other in RESOLVERS.load(deps.storage)? {
+        let deposit = RESOLVER_DEPOSITS.load(deps.storage, &other)?;
+        filled = filled.checked_add(deposit.partial_amount)?;
+        if other == *resolver {
+            return Ok(secret_index_for_fill(immutables, filled));
+        }
+    }
+    Err(StdError::not_found("resolver"))
+}
+
+fn secret_index_for_fill(immutables: &Immutables, filled: Uint128) -> u16 {
+    match immutables.parts_amount {
+        None => 0,
+        Some(parts_amount) => merkle::secret_index(filled, immutables.amount, parts_amount),
+    }
+}
+
+/// Marks the resolvers as paid; the escrow is withdrawn once fills covering the whole amount
+/// have been paid out. A partly filled escrow stays active so the maker can cancel it and
+/// get the unfilled rest back.
+fn mark_withdrawn(
+    deps: DepsMut,
+    state: &mut EscrowState,
+    resolvers: &[Addr],
+) -> Result<(), ContractError> {
+    for resolver in resolvers {
+        let mut deposit = RESOLVER_DEPOSITS.load(deps.storage, resolver)?;
+        deposit.withdrawn = true;
+        RESOLVER_DEPOSITS.save(deps.storage, resolver, &deposit)?;
+        state.total_partial_withdrawn = state.total_partial_withdrawn.checked_add(deposit.partial_amount)?;
+    }
+    
+    if state.total_partial_withdrawn >= state.amount {
+        state.funds_distributed = true;
+        state.state = State::Withdrawn;
+    }
+    ESCROW_STATE.save(deps.storage, state)?;
+    Ok(())
+}
+
+/// Checks that a destination escrow holds every outstanding fill and safety deposit, so the
+/// maker is never paid less than promised
+fn ensure_destination_funded(
+    deps: Deps,
+    env: &Env,
+    state: &EscrowState,
+    immutables: &Immutables,
+) -> Result<(), ContractError> {
+    if !matches!(state.escrow_type, EscrowType::Destination) {
+        return Ok(());
+    }
+    
+    let config = CONFIG.load(deps.storage)?;
+    let deposit_asset = AssetInfo::Native(config.safety_deposit_denom);
+    
+    let mut safety_deposits = Uint128::zero();
+    for resolver in RESOLVERS.load(deps.storage)? {
+        let deposit = RESOLVER_DEPOSITS.load(deps.storage, &resolver)?;
+        if !deposit.withdrawn {
+            safety_deposits = safety_deposits.checked_add(deposit.safety_deposit)?;
+        }
+    }
+    let fills = state.total_partial_amount.checked_sub(state.total_partial_withdrawn)?;
+    
+    let balance = immutables.token.query_balance(&deps.querier, &env.contract.address)?;
+    let required = if immutables.token == deposit_asset {
+        fills.checked_add(safety_deposits)?
+    } else {
+        let deposit_balance = deposit_asset.query_balance(&deps.querier, &env.contract.address)?;
+        if deposit_balance < safety_deposits {
+            return Err(ContractError::NotAllResolversDeposited {});
+        }
+        fills
+    };
+    
+    if balance < required {
+        return Err(ContractError::NotAllResolversDeposited {});
+    }
+    Ok(())
+}
+
 fn calculate_total_safety_deposits(
     deps: Deps,
     resolvers: &[Addr],
@@ -470,11 +882,13 @@ fn calculate_total_safety_deposits(
 
 fn distribute_source_funds(
     deps: Deps,
-    state: &EscrowState,
+    immutables: &Immutables,
     resolvers: &[Addr],
     caller_reward: Uint128,
 ) -> StdResult<Vec<CosmosMsg>> {
     let mut messages = vec![];
+    let config = CONFIG.load(deps.storage)?;
+    let deposit_asset = AssetInfo::Native(config.safety_deposit_denom);
     
     for resolver in resolvers {
         if let Some(deposit) = RESOLVER_DEPOSITS.may_load(deps.storage, resolver)? {
@@ -488,29 +902,16 @@ fn distribute_source_funds(
             }
             
             // Send tokens to resolver
-            if state.immutables.token == "uosmo" {
-                // Native token
+            if immutables.token == deposit_asset {
+                // Same denom as the safety deposit, send both in one transfer
                 let total_amount = resolver_amount.checked_add(actual_deposit)?;
-                messages.push(CosmosMsg::Bank(BankMsg::Send {
-                    to_address: resolver.to_string(),
-                    amount: vec![Coin {
-                        denom: "uosmo".to_string(),
-                        amount: total_amount,
-                    }],
-                }));
+                messages.push(deposit_asset.transfer_msg(resolver, total_amount)?);
             } else {
-                // For CW20 tokens, we would need to handle differently
-                // For now, assuming native tokens only
+                messages.push(immutables.token.transfer_msg(resolver, resolver_amount)?);
                 
                 // Return safety deposit
                 if !actual_deposit.is_zero() {
-                    messages.push(CosmosMsg::Bank(BankMsg::Send {
-                        to_address: resolver.to_string(),
-                        amount: vec![Coin {
-                            denom: "uosmo".to_string(),
-                            amount: actual_deposit,
-                        }],
-                    }));
+                    messages.push(deposit_asset.transfer_msg(resolver, actual_deposit)?);
                 }
             }
         }
@@ -521,25 +922,22 @@ fn distribute_source_funds(
 
 fn distribute_destination_funds(
     deps: Deps,
-    state: &EscrowState,
+    immutables: &Immutables,
     resolvers: &[Addr],
     caller_reward: Uint128,
 ) -> StdResult<Vec<CosmosMsg>> {
     let mut messages = vec![];
+    let config = CONFIG.load(deps.storage)?;
+    let deposit_asset = AssetInfo::Native(config.safety_deposit_denom);
     
-    // Send all tokens to user (maker)
-    if state.immutables.token == "uosmo" {
-        // Native token
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: state.immutables.maker.to_string(),
-            amount: vec![Coin {
-                denom: "uosmo".to_string(),
-                amount: state.total_partial_amount,
-            }],
-        }));
-    } else {
-        // For CW20 tokens, we would need to handle differently
+    // Send the resolvers' fills to user (maker)
+    let mut fill_amount = Uint128::zero();
+    for resolver in resolvers {
+        if let Some(deposit) = RESOLVER_DEPOSITS.may_load(deps.storage, resolver)? {
+            fill_amount = fill_amount.checked_add(deposit.partial_amount)?;
+        }
     }
+    messages.push(immutables.token.transfer_msg(&immutables.maker, fill_amount)?);
     
     // Return safety deposits to resolvers
     for resolver in resolvers {
@@ -553,13 +951,7 @@ fn distribute_destination_funds(
             }
             
             if !actual_deposit.is_zero() {
-                messages.push(CosmosMsg::Bank(BankMsg::Send {
-                    to_address: resolver.to_string(),
-                    amount: vec![Coin {
-                        denom: "uosmo".to_string(),
-                        amount: actual_deposit,
-                    }],
-                }));
+                messages.push(deposit_asset.transfer_msg(resolver, actual_deposit)?);
             }
         }
     }
@@ -568,26 +960,46 @@ fn distribute_destination_funds(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetEscrowState {} => to_json_binary(&query_escrow_state(deps)?),
         QueryMsg::GetResolverCount {} => to_json_binary(&query_resolver_count(deps)?),
         QueryMsg::GetResolver { index } => to_json_binary(&query_resolver(deps, index)?),
         QueryMsg::GetResolverInfo { resolver } => to_json_binary(&query_resolver_info(deps, resolver)?),
+        QueryMsg::GetConfig {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::GetUsedSecretIndices {} => to_json_binary(&query_used_secret_indices(deps)?),
+        QueryMsg::GetCurrentStage { immutables } => {
+            to_json_binary(&query_current_stage(deps, env, *immutables)?)
+        }
     }
 }
 
+fn query_current_stage(deps: Deps, env: Env, immutables: Immutables) -> StdResult<Stage> {
+    let state = ESCROW_STATE.load(deps.storage)?;
+    if hex::encode(immutables.hash()) != state.immutables_hash {
+        return Err(StdError::generic_err("Invalid immutables"));
+    }
+    Ok(immutables.timelocks.stage(&state.escrow_type, env.block.time.seconds()))
+}
+
+fn query_used_secret_indices(deps: Deps) -> StdResult<Vec<u16>> {
+    USED_SECRET_INDICES
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect()
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        safety_deposit_denom: config.safety_deposit_denom,
+    })
+}
+
 fn query_escrow_state(deps: Deps) -> StdResult<EscrowStateResponse> {
     let state = ESCROW_STATE.load(deps.storage)?;
     Ok(EscrowStateResponse {
-        order_hash: state.immutables.order_hash,
-        hashlock: state.immutables.hashlock,
-        maker: state.immutables.maker,
-        taker: state.immutables.taker,
-        token: state.immutables.token,
-        amount: state.immutables.amount,
-        safety_deposit: state.immutables.safety_deposit,
-        timelocks: state.immutables.timelocks,
+        immutables_hash: state.immutables_hash,
+        deployed_at: state.deployed_at,
         is_source: matches!(state.escrow_type, EscrowType::Source),
         src_cancellation_timestamp: state.src_cancellation_timestamp,
         state: state.state,
@@ -621,5 +1033,6 @@ fn query_resolver_info(deps: Deps, resolver: Addr) -> StdResult<ResolverInfoResp
         partial_amount: deposit.partial_amount,
         safety_deposit: deposit.safety_deposit,
         withdrawn: deposit.withdrawn,
+        funded: deposit.funded,
     })
 }
\ No newline at end of file
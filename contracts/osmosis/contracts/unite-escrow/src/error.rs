@@ -24,6 +24,21 @@ pub enum ContractError {
     #[error("Invalid secret")]
     InvalidSecret {},
 
+    #[error("Invalid secret index")]
+    InvalidSecretIndex {},
+
+    #[error("Secret index already used")]
+    SecretIndexUsed {},
+
+    #[error("Secret already revealed")]
+    SecretAlreadyRevealed {},
+
+    #[error("Secret not revealed")]
+    SecretNotRevealed {},
+
+    #[error("Invalid merkle proof")]
+    InvalidMerkleProof {},
+
     #[error("Already withdrawn")]
     AlreadyWithdrawn {},
 
@@ -39,12 +54,30 @@ pub enum ContractError {
     #[error("Invalid partial amount")]
     InvalidPartialAmount {},
 
-    #[error("Invalid time")]
-    InvalidTime {},
+    #[error("Finality lock has not elapsed")]
+    FinalityLock {},
+
+    #[error("Only the taker or a resolver can withdraw during private withdrawal")]
+    PrivateWithdrawalOnly {},
 
-    #[error("Invalid caller")]
-    InvalidCaller {},
+    #[error("Withdrawal period has ended")]
+    WithdrawalPeriodEnded {},
+
+    #[error("Cancellation period has not started")]
+    CancellationNotStarted {},
+
+    #[error("Only the maker, taker or a resolver can cancel during private cancellation")]
+    PrivateCancellationOnly {},
 
     #[error("First resolver already set")]
     FirstResolverAlreadySet {},
+
+    #[error("Not all resolvers have deposited")]
+    NotAllResolversDeposited {},
+
+    #[error("Invalid token")]
+    InvalidToken {},
+
+    #[error("Invalid amount")]
+    InvalidAmount {},
 }
@@ -1,7 +1,11 @@
 pub mod contract;
 pub mod error;
+pub mod merkle;
 pub mod msg;
 pub mod state;
 pub mod types;
 
+#[cfg(test)]
+mod tests;
+
 pub use crate::error::ContractError;
@@ -0,0 +1,46 @@
+//! Merkle tree of secrets for orders filled in several parts.
+//!
+//! An order split into `parts_amount` parts commits to `parts_amount + 1` secrets. The
+//! hashlock is the root of a tree whose leaves are `sha256(index_be_u64 || sha256(secret))`,
+//! inner nodes hash their children in sorted order, so proofs carry no left/right flags.
+
+use cosmwasm_std::Uint128;
+use sha2::{Digest, Sha256};
+
+pub type Hash = [u8; 32];
+
+pub fn sha256(data: &[u8]) -> Hash {
+    Sha256::digest(data).into()
+}
+
+/// Leaf committing to the secret at `index`
+pub fn leaf(index: u16, secret_hash: &Hash) -> Hash {
+    let mut data = [0u8; 40];
+    data[..8].copy_from_slice(&(index as u64).to_be_bytes());
+    data[8..].copy_from_slice(secret_hash);
+    sha256(&data)
+}
+
+pub fn hash_pair(a: &Hash, b: &Hash) -> Hash {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut data = [0u8; 64];
+    data[..32].copy_from_slice(first);
+    data[32..].copy_from_slice(second);
+    sha256(&data)
+}
+
+/// Folds `proof` onto `leaf` and returns the resulting root
+pub fn process_proof(leaf: Hash, proof: &[Hash]) -> Hash {
+    proof.iter().fold(leaf, |node, sibling| hash_pair(&node, sibling))
+}
+
+/// Index of the secret unlocking a fill that brings the order to `filled` out of `total`.
+/// The last secret is reserved for the fill completing the order.
+pub fn secret_index(filled: Uint128, total: Uint128, parts_amount: u16) -> u16 {
+    if filled >= total {
+        return parts_amount;
+    }
+    (filled - Uint128::one())
+        .multiply_ratio(parts_amount, total)
+        .u128() as u16
+}
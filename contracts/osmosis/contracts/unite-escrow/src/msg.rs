@@ -1,4 +1,5 @@
 use cosmwasm_std::{Addr, Uint128};
+use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use crate::types::{Immutables, EscrowType, State};
@@ -11,6 +12,8 @@ pub struct EscrowInstantiateMsg {
     pub immutables: Immutables,
     pub escrow_type: EscrowType,
     pub src_cancellation_timestamp: Option<u64>,
+    /// Bank denom in which resolver safety deposits and caller rewards are paid
+    pub safety_deposit_denom: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -28,9 +31,24 @@ pub enum ExecuteMsg {
         resolver: Addr,
         partial_amount: Uint128,
     },
+    /// Reveals a secret, unlocking the fills it covers for `ClaimResolverShare`
+    RevealSecret {
+        secret: String,
+        immutables: Immutables,
+        /// Secret index and merkle proof, required when `immutables.parts_amount` is set
+        index: Option<u16>,
+        proof: Option<Vec<String>>,
+    },
+    /// Reveals a secret and pays out every fill it covers
     WithdrawWithSecret {
         secret: String,
         immutables: Immutables,
+        index: Option<u16>,
+        proof: Option<Vec<String>>,
+    },
+    /// Pays out the sender's own fill once its secret has been revealed
+    ClaimResolverShare {
+        immutables: Immutables,
     },
     Cancel {
         immutables: Immutables,
@@ -41,6 +59,22 @@ pub enum ExecuteMsg {
         resolver_deposit: Uint128,
     },
     MarkUserFunded {},
+    /// Locks the attached native tokens in the escrow, as `Cw20HookMsg::Fund` does for CW20
+    Fund {
+        immutables: Immutables,
+    },
+    Receive(Cw20ReceiveMsg),
+}
+
+/// Messages accepted through the CW20 `Send` hook
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Lock the sent tokens in the escrow. On source escrows this is the maker's
+    /// deposit, on destination escrows it is a resolver's partial fill.
+    Fund {
+        immutables: Immutables,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -50,18 +84,20 @@ pub enum QueryMsg {
     GetResolverCount {},
     GetResolver { index: u32 },
     GetResolverInfo { resolver: Addr },
+    GetConfig {},
+    /// Secret indices already revealed; a single-secret escrow uses index 0
+    GetUsedSecretIndices {},
+    /// Timelock stage at the current block time
+    GetCurrentStage {
+        immutables: Box<Immutables>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct EscrowStateResponse {
-    pub order_hash: String,
-    pub hashlock: String,
-    pub maker: Addr,
-    pub taker: Addr,
-    pub token: String,
-    pub amount: Uint128,
-    pub safety_deposit: Uint128,
-    pub timelocks: Timelocks,
+    /// Hex `Immutables::hash` of the escrow
+    pub immutables_hash: String,
+    pub deployed_at: u64,
     pub is_source: bool,
     pub src_cancellation_timestamp: Option<u64>,
     pub state: State,
@@ -72,11 +108,16 @@ pub struct EscrowStateResponse {
     pub factory: Addr,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub safety_deposit_denom: String,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ResolverInfoResponse {
     pub partial_amount: Uint128,
     pub safety_deposit: Uint128,
     pub withdrawn: bool,
+    pub funded: Uint128,
 }
 
-use crate::types::Timelocks;
@@ -1,18 +1,30 @@
 use cosmwasm_std::{Addr, Uint128};
 use cw_storage_plus::{Item, Map};
 use serde::{Deserialize, Serialize};
-use crate::types::{Immutables, EscrowType, State};
+use crate::types::{EscrowType, State};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Config {
+    pub safety_deposit_denom: String,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct EscrowState {
-    pub immutables: Immutables,
+    /// Hex `Immutables::hash`, every call passing immutables is checked against it
+    pub immutables_hash: String,
     pub escrow_type: EscrowType,
     pub src_cancellation_timestamp: Option<u64>,
     pub state: State,
     pub deployed_at: u64,
+    /// `immutables.amount`, which the resolvers' fills may not exceed in total
+    pub amount: Uint128,
+    /// `immutables.parts_amount`, used to find the secret a new fill would be locked by
+    pub parts_amount: Option<u16>,
     pub total_partial_amount: Uint128,
     pub total_partial_withdrawn: Uint128,
     pub funds_distributed: bool,
+    /// Escrowed CW20 tokens received from the maker so far
+    pub funded_amount: Uint128,
     pub user_funded: bool,
     pub factory: Addr,
 }
@@ -22,8 +34,12 @@ pub struct ResolverDeposit {
     pub partial_amount: Uint128,
     pub safety_deposit: Uint128,
     pub withdrawn: bool,
+    /// Escrowed tokens the resolver has deposited into a destination escrow
+    pub funded: Uint128,
 }
 
+pub const CONFIG: Item<Config> = Item::new("config");
 pub const ESCROW_STATE: Item<EscrowState> = Item::new("escrow_state");
 pub const RESOLVER_DEPOSITS: Map<&Addr, ResolverDeposit> = Map::new("resolver_deposits");
 pub const RESOLVERS: Item<Vec<Addr>> = Item::new("resolvers");
+pub const USED_SECRET_INDICES: Map<u16, bool> = Map::new("used_secret_indices");
@@ -0,0 +1,1140 @@
+use cosmwasm_std::{coins, to_json_binary, Addr, Empty, Uint128};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg};
+use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+use sha2::{Digest, Sha256};
+
+use crate::merkle;
+use crate::msg::{
+    Cw20HookMsg, EscrowInstantiateMsg, EscrowStateResponse, ExecuteMsg, QueryMsg, ResolverInfoResponse,
+};
+use crate::types::{AssetInfo, EscrowType, Immutables, Stage, State, Timelocks};
+use crate::ContractError;
+
+const MAKER: &str = "maker";
+const TAKER: &str = "taker";
+const FACTORY: &str = "factory";
+const RESOLVER_1: &str = "resolver1";
+const RESOLVER_2: &str = "resolver2";
+const DENOM: &str = "uosmo";
+const IBC_USDC: &str = "ibc/498A0751C798A0D9A389AA3691123DADA57DAA4FE165D5C75894505B876BA6E4";
+const SAFETY_DEPOSIT: u128 = 100;
+const SECRET: &str = "0101010101010101010101010101010101010101010101010101010101010101";
+
+fn escrow_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    ))
+}
+
+fn cw20_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    ))
+}
+
+/// CW20 token with `amount` minted to each of `holders`
+fn cw20_token(app: &mut App, symbol: &str, holders: &[&str], amount: u128) -> Addr {
+    let code_id = app.store_code(cw20_contract());
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(MAKER),
+        &cw20_base::msg::InstantiateMsg {
+            name: symbol.to_string(),
+            symbol: symbol.to_string(),
+            decimals: 6,
+            initial_balances: holders
+                .iter()
+                .map(|holder| Cw20Coin {
+                    address: holder.to_string(),
+                    amount: Uint128::new(amount),
+                })
+                .collect(),
+            mint: None,
+            marketing: None,
+        },
+        &[],
+        symbol,
+        None,
+    )
+    .unwrap()
+}
+
+fn hashlock() -> String {
+    hex::encode(Sha256::digest(hex::decode(SECRET).unwrap()))
+}
+
+fn timelocks() -> Timelocks {
+    Timelocks::new([0, 1000, 2000, 3000, 0, 1000, 2000])
+}
+
+struct Suite {
+    app: App,
+    token: Addr,
+    escrow: Addr,
+    immutables: Immutables,
+}
+
+impl Suite {
+    fn new(escrow_type: EscrowType, amount: u128) -> Self {
+        Self::build(escrow_type, amount, hashlock(), None, false)
+    }
+
+    fn with_hashlock(
+        escrow_type: EscrowType,
+        amount: u128,
+        hashlock: String,
+        parts_amount: Option<u16>,
+    ) -> Self {
+        Self::build(escrow_type, amount, hashlock, parts_amount, false)
+    }
+
+    /// Escrow of the native safety deposit denom instead of the CW20 token
+    fn native(escrow_type: EscrowType, amount: u128) -> Self {
+        Self::build(escrow_type, amount, hashlock(), None, true)
+    }
+
+    fn build(
+        escrow_type: EscrowType,
+        amount: u128,
+        hashlock: String,
+        parts_amount: Option<u16>,
+        native: bool,
+    ) -> Self {
+        let mut app = AppBuilder::new().build(|router, _, storage| {
+            for addr in [MAKER, FACTORY, RESOLVER_1, RESOLVER_2] {
+                router
+                    .bank
+                    .init_balance(storage, &Addr::unchecked(addr), coins(1_000, DENOM))
+                    .unwrap();
+            }
+        });
+
+        let token = cw20_token(&mut app, "MUSDT", &[MAKER, RESOLVER_1, RESOLVER_2], 10_000);
+
+        let mut immutables = Immutables {
+            order_hash: "order".to_string(),
+            hashlock,
+            maker: Addr::unchecked(MAKER),
+            taker: Addr::unchecked(TAKER),
+            token: if native {
+                AssetInfo::Native(DENOM.to_string())
+            } else {
+                AssetInfo::Cw20(token.clone())
+            },
+            amount: Uint128::new(amount),
+            safety_deposit: Uint128::new(SAFETY_DEPOSIT),
+            timelocks: timelocks(),
+            parts_amount,
+        };
+
+        let escrow_code_id = app.store_code(escrow_contract());
+        let escrow = app
+            .instantiate_contract(
+                escrow_code_id,
+                Addr::unchecked(FACTORY),
+                &EscrowInstantiateMsg {
+                    immutables: immutables.clone(),
+                    escrow_type: escrow_type.clone(),
+                    src_cancellation_timestamp: None,
+                    safety_deposit_denom: DENOM.to_string(),
+                },
+                &[],
+                "escrow",
+                None,
+            )
+            .unwrap();
+
+        let init_msg = match escrow_type {
+            EscrowType::Source => ExecuteMsg::Initialize {
+                immutables: immutables.clone(),
+                is_source: true,
+            },
+            EscrowType::Destination => ExecuteMsg::InitializeDst {
+                immutables: immutables.clone(),
+                src_cancellation_timestamp: app.block_info().time.seconds(),
+            },
+        };
+        app.execute_contract(Addr::unchecked(FACTORY), escrow.clone(), &init_msg, &[])
+            .unwrap();
+        // Later calls present the immutables as recorded at initialization
+        immutables
+            .timelocks
+            .set_deployed_at(app.block_info().time.seconds());
+
+        Suite {
+            app,
+            token,
+            escrow,
+            immutables,
+        }
+    }
+
+    /// Registers two resolvers filling 600 and 400 units respectively
+    fn add_resolvers(&mut self) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(FACTORY),
+                self.escrow.clone(),
+                &ExecuteMsg::HandleFirstResolver {
+                    resolver: Addr::unchecked(RESOLVER_1),
+                    partial_amount: Uint128::new(600),
+                    resolver_deposit: Uint128::new(SAFETY_DEPOSIT),
+                },
+                &coins(SAFETY_DEPOSIT, DENOM),
+            )
+            .unwrap();
+        self.app
+            .execute_contract(
+                Addr::unchecked(FACTORY),
+                self.escrow.clone(),
+                &ExecuteMsg::AddResolverSafetyDeposit {
+                    resolver: Addr::unchecked(RESOLVER_2),
+                    partial_amount: Uint128::new(400),
+                },
+                &coins(SAFETY_DEPOSIT, DENOM),
+            )
+            .unwrap();
+    }
+
+    fn join(&mut self, sender: &str, resolver: &str, partial_amount: u128) -> anyhow::Result<()> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(sender),
+                self.escrow.clone(),
+                &ExecuteMsg::AddResolverSafetyDeposit {
+                    resolver: Addr::unchecked(resolver),
+                    partial_amount: Uint128::new(partial_amount),
+                },
+                &coins(SAFETY_DEPOSIT, DENOM),
+            )
+            .map(|_| ())
+    }
+
+    fn fund(&mut self, from: &str, amount: u128) -> anyhow::Result<()> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(from),
+                self.token.clone(),
+                &Cw20ExecuteMsg::Send {
+                    contract: self.escrow.to_string(),
+                    amount: Uint128::new(amount),
+                    msg: to_json_binary(&Cw20HookMsg::Fund {
+                        immutables: self.immutables.clone(),
+                    })
+                    .unwrap(),
+                },
+                &[],
+            )
+            .map(|_| ())
+    }
+
+    fn fund_native(&mut self, from: &str, amount: u128) -> anyhow::Result<()> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(from),
+                self.escrow.clone(),
+                &ExecuteMsg::Fund {
+                    immutables: self.immutables.clone(),
+                },
+                &coins(amount, DENOM),
+            )
+            .map(|_| ())
+    }
+
+    fn send_native(&mut self, from: &str, amount: u128) {
+        self.app
+            .send_tokens(Addr::unchecked(from), self.escrow.clone(), &coins(amount, DENOM))
+            .unwrap();
+    }
+
+    fn cancel(&mut self, sender: &str) -> anyhow::Result<()> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(sender),
+                self.escrow.clone(),
+                &ExecuteMsg::Cancel {
+                    immutables: self.immutables.clone(),
+                },
+                &[],
+            )
+            .map(|_| ())
+    }
+
+    fn token_balance(&self, address: &str) -> u128 {
+        let res: BalanceResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                &self.token,
+                &Cw20QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap();
+        res.balance.u128()
+    }
+
+    fn native_balance(&self, address: &str) -> u128 {
+        self.app.wrap().query_balance(address, DENOM).unwrap().amount.u128()
+    }
+
+    fn escrow_state(&self) -> EscrowStateResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(&self.escrow, &QueryMsg::GetEscrowState {})
+            .unwrap()
+    }
+
+    fn withdraw(
+        &mut self,
+        sender: &str,
+        secret: &[u8; 32],
+        index: u16,
+        proof: &[merkle::Hash],
+    ) -> anyhow::Result<()> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(sender),
+                self.escrow.clone(),
+                &ExecuteMsg::WithdrawWithSecret {
+                    secret: hex::encode(secret),
+                    immutables: self.immutables.clone(),
+                    index: Some(index),
+                    proof: Some(proof.iter().map(hex::encode).collect()),
+                },
+                &[],
+            )
+            .map(|_| ())
+    }
+
+    fn advance(&mut self, seconds: u64) {
+        self.app
+            .update_block(|block| block.time = block.time.plus_seconds(seconds));
+    }
+}
+
+#[test]
+fn source_escrow_pays_resolvers_in_cw20() {
+    let mut suite = Suite::new(EscrowType::Source, 1000);
+    suite.add_resolvers();
+
+    suite.fund(MAKER, 1000).unwrap();
+    assert!(suite.escrow_state().user_funded);
+    assert_eq!(suite.token_balance(suite.escrow.as_str()), 1000);
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(RESOLVER_1),
+            suite.escrow.clone(),
+            &ExecuteMsg::WithdrawWithSecret {
+                secret: SECRET.to_string(),
+                immutables: suite.immutables.clone(),
+                index: None,
+                proof: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+    assert_eq!(suite.escrow_state().state, State::Withdrawn);
+    assert_eq!(suite.token_balance(suite.escrow.as_str()), 0);
+    assert_eq!(suite.token_balance(RESOLVER_1), 10_600);
+    assert_eq!(suite.token_balance(RESOLVER_2), 10_400);
+    // Safety deposits come back in the native denom; the factory forwarded both
+    assert_eq!(suite.native_balance(RESOLVER_1), 1_100);
+    assert_eq!(suite.native_balance(RESOLVER_2), 1_100);
+    assert_eq!(suite.native_balance(suite.escrow.as_str()), 0);
+}
+
+#[test]
+fn partial_cw20_deposits_fund_source_once_complete() {
+    let mut suite = Suite::new(EscrowType::Source, 1000);
+
+    suite.fund(MAKER, 1).unwrap();
+    assert!(!suite.escrow_state().user_funded);
+    suite.fund(MAKER, 998).unwrap();
+    assert!(!suite.escrow_state().user_funded);
+    suite.fund(MAKER, 1).unwrap();
+    assert!(suite.escrow_state().user_funded);
+    assert_eq!(suite.token_balance(suite.escrow.as_str()), 1000);
+}
+
+#[test]
+fn destination_escrow_pays_maker_in_cw20() {
+    let mut suite = Suite::new(EscrowType::Destination, 1000);
+    suite.add_resolvers();
+
+    suite.fund(RESOLVER_1, 600).unwrap();
+    suite.fund(RESOLVER_2, 400).unwrap();
+    assert!(!suite.escrow_state().user_funded);
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(RESOLVER_2),
+            suite.escrow.clone(),
+            &ExecuteMsg::WithdrawWithSecret {
+                secret: SECRET.to_string(),
+                immutables: suite.immutables.clone(),
+                index: None,
+                proof: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+    assert_eq!(suite.token_balance(MAKER), 10_000 + 1000);
+    assert_eq!(suite.token_balance(suite.escrow.as_str()), 0);
+    assert_eq!(suite.native_balance(RESOLVER_1), 1_100);
+    assert_eq!(suite.native_balance(RESOLVER_2), 1_100);
+}
+
+#[test]
+fn cancel_refunds_cw20_to_maker() {
+    let mut suite = Suite::new(EscrowType::Source, 1000);
+    suite.add_resolvers();
+    suite.fund(MAKER, 1000).unwrap();
+    assert_eq!(suite.token_balance(MAKER), 9_000);
+
+    suite.advance(2000);
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(MAKER),
+            suite.escrow.clone(),
+            &ExecuteMsg::Cancel {
+                immutables: suite.immutables.clone(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    assert_eq!(suite.escrow_state().state, State::Cancelled);
+    assert_eq!(suite.token_balance(MAKER), 10_000);
+    assert_eq!(suite.token_balance(suite.escrow.as_str()), 0);
+    assert_eq!(suite.native_balance(RESOLVER_1), 1_100);
+    assert_eq!(suite.native_balance(RESOLVER_2), 1_100);
+}
+
+#[test]
+fn receive_rejects_foreign_token() {
+    let mut suite = Suite::new(EscrowType::Source, 1000);
+
+    let other = cw20_token(&mut suite.app, "MDAI", &[MAKER], 1000);
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(MAKER),
+            other,
+            &Cw20ExecuteMsg::Send {
+                contract: suite.escrow.to_string(),
+                amount: Uint128::new(1000),
+                msg: to_json_binary(&Cw20HookMsg::Fund {
+                    immutables: suite.immutables.clone(),
+                })
+                .unwrap(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::InvalidToken {})
+    ));
+    assert!(!suite.escrow_state().user_funded);
+}
+
+#[test]
+fn source_escrow_pays_resolvers_in_ibc_denom() {
+    let mut app = AppBuilder::new().build(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(MAKER), coins(1_000, IBC_USDC))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(FACTORY), coins(1_000, IBC_USDC))
+            .unwrap();
+    });
+
+    let mut immutables = Immutables {
+        order_hash: "order".to_string(),
+        hashlock: hashlock(),
+        maker: Addr::unchecked(MAKER),
+        taker: Addr::unchecked(TAKER),
+        token: AssetInfo::Native(IBC_USDC.to_string()),
+        amount: Uint128::new(1000),
+        safety_deposit: Uint128::new(SAFETY_DEPOSIT),
+        timelocks: timelocks(),
+        parts_amount: None,
+    };
+
+    let escrow_code_id = app.store_code(escrow_contract());
+    let escrow = app
+        .instantiate_contract(
+            escrow_code_id,
+            Addr::unchecked(FACTORY),
+            &EscrowInstantiateMsg {
+                immutables: immutables.clone(),
+                escrow_type: EscrowType::Source,
+                src_cancellation_timestamp: None,
+                safety_deposit_denom: IBC_USDC.to_string(),
+            },
+            &[],
+            "escrow",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        Addr::unchecked(FACTORY),
+        escrow.clone(),
+        &ExecuteMsg::Initialize {
+            immutables: immutables.clone(),
+            is_source: true,
+        },
+        &[],
+    )
+    .unwrap();
+    immutables
+        .timelocks
+        .set_deployed_at(app.block_info().time.seconds());
+
+    // A deposit in the wrong denom is not counted
+    let err = app
+        .execute_contract(
+            Addr::unchecked(FACTORY),
+            escrow.clone(),
+            &ExecuteMsg::AddResolverSafetyDeposit {
+                resolver: Addr::unchecked(RESOLVER_1),
+                partial_amount: Uint128::new(1000),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::NoSafetyDeposit {})
+    ));
+
+    app.execute_contract(
+        Addr::unchecked(FACTORY),
+        escrow.clone(),
+        &ExecuteMsg::AddResolverSafetyDeposit {
+            resolver: Addr::unchecked(RESOLVER_1),
+            partial_amount: Uint128::new(1000),
+        },
+        &coins(SAFETY_DEPOSIT, IBC_USDC),
+    )
+    .unwrap();
+    app.send_tokens(
+        Addr::unchecked(MAKER),
+        escrow.clone(),
+        &coins(1000, IBC_USDC),
+    )
+    .unwrap();
+
+    app.execute_contract(
+        Addr::unchecked(RESOLVER_1),
+        escrow.clone(),
+        &ExecuteMsg::WithdrawWithSecret {
+            secret: SECRET.to_string(),
+            immutables,
+            index: None,
+            proof: None,
+        },
+        &[],
+    )
+    .unwrap();
+
+    let balance = |address: &str| {
+        app.wrap()
+            .query_balance(address, IBC_USDC)
+            .unwrap()
+            .amount
+            .u128()
+    };
+    // The fill plus the safety deposit the factory forwarded
+    assert_eq!(balance(RESOLVER_1), 1_100);
+    assert_eq!(balance(MAKER), 0);
+    assert_eq!(balance(escrow.as_str()), 0);
+}
+
+#[test]
+fn multi_fill_secrets_pay_each_fill_separately() {
+    // Two parts, so three secrets: 600 fills up to index 1, the remaining 400 completes
+    // the order and uses the last secret
+    let secrets = [[1u8; 32], [2u8; 32], [3u8; 32]];
+    let leaves: Vec<merkle::Hash> = secrets
+        .iter()
+        .enumerate()
+        .map(|(index, secret)| merkle::leaf(index as u16, &merkle::sha256(secret)))
+        .collect();
+    let inner = merkle::hash_pair(&leaves[0], &leaves[1]);
+    let root = merkle::hash_pair(&inner, &leaves[2]);
+
+    let mut suite = Suite::with_hashlock(EscrowType::Source, 1000, hex::encode(root), Some(2));
+    suite.add_resolvers();
+    suite.fund(MAKER, 1000).unwrap();
+
+    let err = suite
+        .withdraw(RESOLVER_1, &secrets[1], 1, &[leaves[2]])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::InvalidMerkleProof {})
+    ));
+    // No fill ends in the first part
+    let err = suite
+        .withdraw(RESOLVER_1, &secrets[0], 0, &[leaves[1], leaves[2]])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::InvalidSecretIndex {})
+    ));
+
+    suite
+        .withdraw(RESOLVER_1, &secrets[1], 1, &[leaves[0], leaves[2]])
+        .unwrap();
+    assert_eq!(suite.token_balance(RESOLVER_1), 10_600);
+    assert_eq!(suite.token_balance(RESOLVER_2), 10_000);
+    assert_eq!(suite.escrow_state().state, State::Active);
+
+    let err = suite
+        .withdraw(RESOLVER_2, &secrets[1], 1, &[leaves[0], leaves[2]])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::SecretIndexUsed {})
+    ));
+
+    suite.withdraw(RESOLVER_2, &secrets[2], 2, &[inner]).unwrap();
+    assert_eq!(suite.token_balance(RESOLVER_2), 10_400);
+    assert_eq!(suite.native_balance(RESOLVER_2), 1_100);
+    assert_eq!(suite.token_balance(suite.escrow.as_str()), 0);
+
+    let state = suite.escrow_state();
+    assert_eq!(state.state, State::Withdrawn);
+    assert_eq!(state.total_partial_withdrawn, Uint128::new(1000));
+    let used: Vec<u16> = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.escrow, &QueryMsg::GetUsedSecretIndices {})
+        .unwrap();
+    assert_eq!(used, vec![1, 2]);
+}
+
+#[test]
+fn later_fills_join_after_an_earlier_secret_is_revealed() {
+    let secrets = [[1u8; 32], [2u8; 32], [3u8; 32]];
+    let leaves: Vec<merkle::Hash> = secrets
+        .iter()
+        .enumerate()
+        .map(|(index, secret)| merkle::leaf(index as u16, &merkle::sha256(secret)))
+        .collect();
+    let inner = merkle::hash_pair(&leaves[0], &leaves[1]);
+    let root = merkle::hash_pair(&inner, &leaves[2]);
+
+    let mut suite = Suite::with_hashlock(EscrowType::Source, 1000, hex::encode(root), Some(2));
+    suite.join(FACTORY, RESOLVER_1, 600).unwrap();
+    suite.fund(MAKER, 1000).unwrap();
+    suite
+        .withdraw(RESOLVER_1, &secrets[1], 1, &[leaves[0], leaves[2]])
+        .unwrap();
+    assert_eq!(suite.token_balance(RESOLVER_1), 10_600);
+
+    // 700 in total would be locked by the revealed second secret
+    let err = suite.join(FACTORY, RESOLVER_2, 100).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::SecretAlreadyRevealed {})
+    ));
+
+    suite.join(FACTORY, RESOLVER_2, 400).unwrap();
+    suite.withdraw(RESOLVER_2, &secrets[2], 2, &[inner]).unwrap();
+    assert_eq!(suite.token_balance(RESOLVER_2), 10_400);
+    assert_eq!(suite.token_balance(suite.escrow.as_str()), 0);
+    assert_eq!(suite.escrow_state().state, State::Withdrawn);
+}
+
+#[test]
+fn resolvers_claim_their_own_share() {
+    let mut suite = Suite::new(EscrowType::Source, 1000);
+    suite.add_resolvers();
+    suite.fund(MAKER, 1000).unwrap();
+
+    let claim = |suite: &mut Suite, sender: &str| {
+        suite.app.execute_contract(
+            Addr::unchecked(sender),
+            suite.escrow.clone(),
+            &ExecuteMsg::ClaimResolverShare {
+                immutables: suite.immutables.clone(),
+            },
+            &[],
+        )
+    };
+
+    let err = claim(&mut suite, RESOLVER_1).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::SecretNotRevealed {})
+    ));
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(TAKER),
+            suite.escrow.clone(),
+            &ExecuteMsg::RevealSecret {
+                secret: SECRET.to_string(),
+                immutables: suite.immutables.clone(),
+                index: None,
+                proof: None,
+            },
+            &[],
+        )
+        .unwrap();
+    // Revealing moves no funds
+    assert_eq!(suite.token_balance(suite.escrow.as_str()), 1000);
+
+    claim(&mut suite, RESOLVER_2).unwrap();
+    assert_eq!(suite.token_balance(RESOLVER_2), 10_400);
+    assert_eq!(suite.native_balance(RESOLVER_2), 1_100);
+    let state = suite.escrow_state();
+    assert_eq!(state.state, State::Active);
+    assert_eq!(state.total_partial_withdrawn, Uint128::new(400));
+
+    let err = claim(&mut suite, RESOLVER_2).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::AlreadyWithdrawn {})
+    ));
+    let err = claim(&mut suite, MAKER).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::Unauthorized {})
+    ));
+
+    claim(&mut suite, RESOLVER_1).unwrap();
+    assert_eq!(suite.token_balance(RESOLVER_1), 10_600);
+    assert_eq!(suite.native_balance(RESOLVER_1), 1_100);
+    assert_eq!(suite.token_balance(suite.escrow.as_str()), 0);
+    assert_eq!(suite.escrow_state().state, State::Withdrawn);
+}
+
+#[test]
+fn resolvers_join_only_through_factory_while_open() {
+    let mut suite = Suite::new(EscrowType::Source, 1000);
+    suite.join(FACTORY, RESOLVER_1, 600).unwrap();
+
+    let assert_err = |res: anyhow::Result<()>, expected: ContractError| {
+        let err = res.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ContractError>().map(ToString::to_string),
+            Some(expected.to_string())
+        );
+    };
+
+    // Registering directly, bypassing the factory
+    assert_err(suite.join(RESOLVER_2, RESOLVER_2, 400), ContractError::Unauthorized {});
+    // Fills may not add up to more than the escrowed amount
+    assert_err(suite.join(FACTORY, RESOLVER_2, 401), ContractError::InvalidPartialAmount {});
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(RESOLVER_1),
+            suite.escrow.clone(),
+            &ExecuteMsg::RevealSecret {
+                secret: SECRET.to_string(),
+                immutables: suite.immutables.clone(),
+                index: None,
+                proof: None,
+            },
+            &[],
+        )
+        .unwrap();
+    assert_err(suite.join(FACTORY, RESOLVER_2, 400), ContractError::SecretAlreadyRevealed {});
+}
+
+#[test]
+fn resolvers_cannot_join_cancelled_escrow() {
+    let mut suite = Suite::new(EscrowType::Source, 1000);
+    suite.join(FACTORY, RESOLVER_1, 600).unwrap();
+    suite.advance(3000);
+    suite.cancel(MAKER).unwrap();
+
+    let err = suite.join(FACTORY, RESOLVER_2, 400).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::InvalidState {})
+    ));
+}
+
+#[test]
+fn cancel_pays_revealed_shares_to_resolvers() {
+    // As in the multi-fill test: RESOLVER_1's 600 uses secret 1, RESOLVER_2's 400 secret 2
+    let secrets = [[1u8; 32], [2u8; 32], [3u8; 32]];
+    let leaves: Vec<merkle::Hash> = secrets
+        .iter()
+        .enumerate()
+        .map(|(index, secret)| merkle::leaf(index as u16, &merkle::sha256(secret)))
+        .collect();
+    let root = merkle::hash_pair(&merkle::hash_pair(&leaves[0], &leaves[1]), &leaves[2]);
+
+    let mut suite = Suite::with_hashlock(EscrowType::Source, 1000, hex::encode(root), Some(2));
+    suite.add_resolvers();
+    suite.fund(MAKER, 1000).unwrap();
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(RESOLVER_1),
+            suite.escrow.clone(),
+            &ExecuteMsg::RevealSecret {
+                secret: hex::encode(secrets[1]),
+                immutables: suite.immutables.clone(),
+                index: Some(1),
+                proof: Some(vec![hex::encode(leaves[0]), hex::encode(leaves[2])]),
+            },
+            &[],
+        )
+        .unwrap();
+
+    suite.advance(3000);
+    suite.cancel(MAKER).unwrap();
+
+    // The revealed fill goes to its resolver, not back to the maker
+    assert_eq!(suite.token_balance(RESOLVER_1), 10_600);
+    assert_eq!(suite.token_balance(RESOLVER_2), 10_000);
+    assert_eq!(suite.token_balance(MAKER), 9_400);
+    assert_eq!(suite.token_balance(suite.escrow.as_str()), 0);
+}
+
+#[test]
+fn partly_filled_escrow_can_be_cancelled_after_withdrawal() {
+    let mut suite = Suite::new(EscrowType::Source, 1000);
+    suite.join(FACTORY, RESOLVER_1, 600).unwrap();
+    suite.fund(MAKER, 1000).unwrap();
+
+    suite.withdraw(RESOLVER_1, &[1u8; 32], 0, &[]).unwrap();
+    assert_eq!(suite.token_balance(RESOLVER_1), 10_600);
+    // The unfilled 400 are still the maker's
+    let state = suite.escrow_state();
+    assert_eq!(state.state, State::Active);
+    assert_eq!(state.total_partial_withdrawn, Uint128::new(600));
+
+    suite.advance(3000);
+    suite.cancel(MAKER).unwrap();
+    assert_eq!(suite.token_balance(MAKER), 9_400);
+    assert_eq!(suite.token_balance(RESOLVER_1), 10_600);
+    assert_eq!(suite.token_balance(suite.escrow.as_str()), 0);
+    assert_eq!(suite.escrow_state().state, State::Cancelled);
+}
+
+#[test]
+fn timelock_stages_gate_withdrawal_and_cancellation() {
+    let mut suite = Suite::new(EscrowType::Source, 1000);
+    suite.add_resolvers();
+    suite.fund(MAKER, 1000).unwrap();
+
+    let stage = |suite: &Suite| -> Stage {
+        suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                &suite.escrow,
+                &QueryMsg::GetCurrentStage {
+                    immutables: Box::new(suite.immutables.clone()),
+                },
+            )
+            .unwrap()
+    };
+    let reveal = |suite: &mut Suite, sender: &str| {
+        suite.app.execute_contract(
+            Addr::unchecked(sender),
+            suite.escrow.clone(),
+            &ExecuteMsg::RevealSecret {
+                secret: SECRET.to_string(),
+                immutables: suite.immutables.clone(),
+                index: None,
+                proof: None,
+            },
+            &[],
+        )
+    };
+    let cancel = |suite: &mut Suite, sender: &str| {
+        suite.app.execute_contract(
+            Addr::unchecked(sender),
+            suite.escrow.clone(),
+            &ExecuteMsg::Cancel {
+                immutables: suite.immutables.clone(),
+            },
+            &[],
+        )
+    };
+    let assert_err = |res: anyhow::Result<_>, expected: ContractError| {
+        let err = res.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ContractError>().map(ToString::to_string),
+            Some(expected.to_string())
+        );
+    };
+
+    assert_eq!(stage(&suite), Stage::PrivateWithdrawal);
+    assert_err(reveal(&mut suite, "stranger"), ContractError::PrivateWithdrawalOnly {});
+    assert_err(cancel(&mut suite, MAKER), ContractError::CancellationNotStarted {});
+
+    suite.advance(1000);
+    assert_eq!(stage(&suite), Stage::PublicWithdrawal);
+
+    suite.advance(1000);
+    assert_eq!(stage(&suite), Stage::PrivateCancellation);
+    assert_err(reveal(&mut suite, RESOLVER_1), ContractError::WithdrawalPeriodEnded {});
+    assert_err(cancel(&mut suite, "stranger"), ContractError::PrivateCancellationOnly {});
+
+    suite.advance(1000);
+    assert_eq!(stage(&suite), Stage::PublicCancellation);
+    cancel(&mut suite, "stranger").unwrap();
+    assert_eq!(suite.escrow_state().state, State::Cancelled);
+}
+
+#[test]
+fn stranger_cannot_register_into_private_windows() {
+    let mut suite = Suite::new(EscrowType::Source, 1000);
+    suite.join(FACTORY, RESOLVER_1, 600).unwrap();
+    suite.fund(MAKER, 1000).unwrap();
+
+    // RESOLVER_2 is not filling the order and tries to register itself as a taker
+    let err = suite.join(RESOLVER_2, RESOLVER_2, 400).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::Unauthorized {})
+    ));
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(RESOLVER_2),
+            suite.escrow.clone(),
+            &ExecuteMsg::WithdrawWithSecret {
+                secret: SECRET.to_string(),
+                immutables: suite.immutables.clone(),
+                index: None,
+                proof: None,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::PrivateWithdrawalOnly {})
+    ));
+
+    suite.advance(2000);
+    let err = suite.cancel(RESOLVER_2).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::PrivateCancellationOnly {})
+    ));
+    suite.cancel(RESOLVER_1).unwrap();
+}
+
+#[test]
+fn finality_lock_precedes_withdrawal() {
+    let mut timelocks = Timelocks::new([10, 1000, 2000, 3000, 20, 1000, 2000]);
+    timelocks.set_deployed_at(100);
+    assert_eq!(timelocks.stage(&EscrowType::Source, 109), Stage::FinalityLock);
+    assert_eq!(timelocks.stage(&EscrowType::Source, 110), Stage::PrivateWithdrawal);
+    assert_eq!(timelocks.stage(&EscrowType::Destination, 119), Stage::FinalityLock);
+    // Destination escrows stay in private cancellation
+    assert_eq!(timelocks.stage(&EscrowType::Destination, 100_000), Stage::PrivateCancellation);
+}
+
+#[test]
+fn cancel_refunds_native_to_maker() {
+    let mut suite = Suite::native(EscrowType::Source, 1000);
+    suite.add_resolvers();
+    suite.send_native(MAKER, 1000);
+    assert_eq!(suite.native_balance(suite.escrow.as_str()), 1200);
+
+    suite.advance(2000);
+    suite.cancel(MAKER).unwrap();
+
+    assert_eq!(suite.native_balance(MAKER), 1000);
+    assert_eq!(suite.native_balance(RESOLVER_1), 1_100);
+    assert_eq!(suite.native_balance(RESOLVER_2), 1_100);
+    assert_eq!(suite.native_balance(suite.escrow.as_str()), 0);
+}
+
+#[test]
+fn cancel_refunds_cw20_fills_to_resolvers() {
+    let mut suite = Suite::new(EscrowType::Destination, 1000);
+    suite.add_resolvers();
+    suite.fund(RESOLVER_1, 600).unwrap();
+    suite.fund(RESOLVER_2, 400).unwrap();
+
+    suite.advance(2000);
+    suite.cancel(RESOLVER_1).unwrap();
+
+    assert_eq!(suite.escrow_state().state, State::Cancelled);
+    assert_eq!(suite.token_balance(RESOLVER_1), 10_000);
+    assert_eq!(suite.token_balance(RESOLVER_2), 10_000);
+    assert_eq!(suite.token_balance(MAKER), 10_000);
+    assert_eq!(suite.token_balance(suite.escrow.as_str()), 0);
+    assert_eq!(suite.native_balance(RESOLVER_1), 1_100);
+    assert_eq!(suite.native_balance(RESOLVER_2), 1_100);
+}
+
+#[test]
+fn cancel_refunds_native_fills_to_resolvers() {
+    let mut suite = Suite::native(EscrowType::Destination, 1000);
+    suite.add_resolvers();
+    suite.fund_native(RESOLVER_1, 600).unwrap();
+    suite.fund_native(RESOLVER_2, 400).unwrap();
+
+    suite.advance(2000);
+    suite.cancel(RESOLVER_2).unwrap();
+
+    assert_eq!(suite.native_balance(RESOLVER_1), 1_100);
+    assert_eq!(suite.native_balance(RESOLVER_2), 1_100);
+    assert_eq!(suite.native_balance(MAKER), 1_000);
+    assert_eq!(suite.native_balance(suite.escrow.as_str()), 0);
+}
+
+#[test]
+fn cancel_refunds_what_each_resolver_deposited() {
+    let mut suite = Suite::new(EscrowType::Destination, 1000);
+    suite.add_resolvers();
+    // RESOLVER_1 never funds its 600, RESOLVER_2 funds its 400 in two transfers
+    suite.fund(RESOLVER_2, 300).unwrap();
+    suite.fund(RESOLVER_2, 100).unwrap();
+
+    let err = suite.fund(MAKER, 100).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::Unauthorized {})
+    ));
+
+    suite.advance(2000);
+    suite.cancel(RESOLVER_2).unwrap();
+
+    // Refunds follow the deposits, not the order resolvers joined in
+    assert_eq!(suite.token_balance(RESOLVER_1), 10_000);
+    assert_eq!(suite.token_balance(RESOLVER_2), 10_000);
+    assert_eq!(suite.token_balance(suite.escrow.as_str()), 0);
+}
+
+#[test]
+fn cancel_refunds_native_deposits_exactly() {
+    let mut suite = Suite::native(EscrowType::Destination, 1000);
+    suite.add_resolvers();
+    suite.fund_native(RESOLVER_2, 400).unwrap();
+    let info: ResolverInfoResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.escrow,
+            &QueryMsg::GetResolverInfo {
+                resolver: Addr::unchecked(RESOLVER_2),
+            },
+        )
+        .unwrap();
+    assert_eq!(info.funded, Uint128::new(400));
+
+    suite.advance(2000);
+    suite.cancel(RESOLVER_1).unwrap();
+
+    assert_eq!(suite.native_balance(RESOLVER_1), 1_100);
+    assert_eq!(suite.native_balance(RESOLVER_2), 1_100);
+    assert_eq!(suite.native_balance(suite.escrow.as_str()), 0);
+}
+
+#[test]
+fn destination_withdrawal_requires_all_fills() {
+    let mut suite = Suite::new(EscrowType::Destination, 1000);
+    suite.add_resolvers();
+    suite.fund(RESOLVER_1, 600).unwrap();
+    suite.fund(RESOLVER_2, 399).unwrap();
+
+    let withdraw = |suite: &mut Suite| {
+        suite.app.execute_contract(
+            Addr::unchecked(RESOLVER_1),
+            suite.escrow.clone(),
+            &ExecuteMsg::WithdrawWithSecret {
+                secret: SECRET.to_string(),
+                immutables: suite.immutables.clone(),
+                index: None,
+                proof: None,
+            },
+            &[],
+        )
+    };
+
+    let err = withdraw(&mut suite).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::NotAllResolversDeposited {})
+    ));
+
+    suite.fund(RESOLVER_2, 1).unwrap();
+    withdraw(&mut suite).unwrap();
+    assert_eq!(suite.token_balance(MAKER), 11_000);
+}
+
+#[test]
+fn immutables_hash_matches_evm() {
+    // `ImmutablesLib.hash` of the same immutables on the EVM side, i.e.
+    // `keccak256(abi.encode(IBaseEscrow.Immutables(orderHash, hashlock, maker, taker, token,
+    // 1_000_000, 100_000, timelocks)))` with the addresses below packed as uint256
+    let mut immutables = Immutables {
+        order_hash: "4f67f30c1f4fe4ee2acc0044f4999f12dcae93fef99c9a9f3fa716b360c72f8d".to_string(),
+        hashlock: hashlock(),
+        maker: Addr::unchecked("0xAb8483F64d9C6d1EcF9b849Ae677dD3315835cb2"),
+        taker: Addr::unchecked("0x5B38Da6a701c568545dCfcB03FcB875f56beddC4"),
+        token: AssetInfo::Cw20(Addr::unchecked("0x4B20993Bc481177ec7E8f571ceCaE8A9e22C02db")),
+        amount: Uint128::new(1_000_000),
+        safety_deposit: Uint128::new(100_000),
+        timelocks: Timelocks::new([10, 120, 300, 400, 10, 100, 200]),
+        parts_amount: None,
+    };
+    immutables.timelocks.set_deployed_at(1_700_000_000);
+    assert_eq!(
+        hex::encode(immutables.timelocks.0.to_be_bytes()),
+        "6553f100000000c8000000640000000a000001900000012c000000780000000a"
+    );
+    assert_eq!(
+        hex::encode(immutables.hash()),
+        "82d0279e32c9a38411f7bd1580a0c51de8bb504a717406ae995deb7050c0032c"
+    );
+}
+
+#[test]
+fn rejects_tampered_timelocks() {
+    let mut suite = Suite::new(EscrowType::Source, 1000);
+    suite.add_resolvers();
+    suite.fund(MAKER, 1000).unwrap();
+
+    // Pretending cancellation has started
+    let mut immutables = suite.immutables.clone();
+    immutables.timelocks = Timelocks::new([0, 0, 0, 0, 0, 0, 0]);
+    immutables.timelocks.set_deployed_at(suite.immutables.timelocks.get_deployed_at());
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked("stranger"),
+            suite.escrow.clone(),
+            &ExecuteMsg::Cancel { immutables },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::InvalidImmutables {})
+    ));
+}
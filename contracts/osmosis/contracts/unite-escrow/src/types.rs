@@ -1,38 +1,166 @@
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{
+    to_json_binary, Addr, BankMsg, Coin, CosmosMsg, QuerierWrapper, StdResult, Uint128, Uint256,
+    WasmMsg,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use unite_order::hash::{address_word, bytes32_word, keccak256, uint_word};
+use unite_timelocks::{Stage as TimelockStage, Timelocks as PackedTimelocks};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Immutables {
     pub order_hash: String,
+    /// `sha256(secret)`, or the merkle root of the secrets when `parts_amount` is set
     pub hashlock: String,
     pub maker: Addr,
     pub taker: Addr,
-    pub token: String,
+    pub token: AssetInfo,
     pub amount: Uint128,
     pub safety_deposit: Uint128,
     pub timelocks: Timelocks,
+    /// Number of parts a multi-fill order is split into, `None` for a single secret
+    pub parts_amount: Option<u16>,
 }
 
+impl Immutables {
+    /// `keccak256(abi.encode(immutables))`, the same layout as the EVM `ImmutablesLib.hash`.
+    /// Multi-fill orders commit to `parts_amount` as an extra trailing word.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut words = vec![
+            bytes32_word(&self.order_hash),
+            bytes32_word(&self.hashlock),
+            address_word(self.maker.as_str()),
+            address_word(self.taker.as_str()),
+            address_word(&self.token.to_string()),
+            uint_word(self.amount.u128()),
+            uint_word(self.safety_deposit.u128()),
+            self.timelocks.0.to_be_bytes(),
+        ];
+        if let Some(parts_amount) = self.parts_amount {
+            words.push(uint_word(parts_amount.into()));
+        }
+        keccak256(&words.concat())
+    }
+}
+
+/// Escrowed asset: a bank denom (`uosmo`, `ibc/...`, `factory/...`) or a CW20 contract
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct Timelocks {
-    pub src_withdrawal: u64,
-    pub src_public_withdrawal: u64,
-    pub src_cancellation: u64,
-    pub src_public_cancellation: u64,
-    pub dst_withdrawal: u64,
-    pub dst_public_withdrawal: u64,
-    pub dst_cancellation: u64,
-    pub deployed_at: Option<u64>,
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfo {
+    Native(String),
+    Cw20(Addr),
+}
+
+impl AssetInfo {
+    pub fn is_native_denom(&self, denom: &str) -> bool {
+        matches!(self, AssetInfo::Native(native) if native == denom)
+    }
+
+    /// Builds a transfer of `amount` of this asset to `recipient`
+    pub fn transfer_msg(&self, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+        match self {
+            AssetInfo::Native(denom) => Ok(CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![Coin {
+                    denom: denom.clone(),
+                    amount,
+                }],
+            })),
+            AssetInfo::Cw20(contract_addr) => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount,
+                })?,
+                funds: vec![],
+            })),
+        }
+    }
+
+    pub fn query_balance(&self, querier: &QuerierWrapper, address: &Addr) -> StdResult<Uint128> {
+        match self {
+            AssetInfo::Native(denom) => Ok(querier.query_balance(address, denom)?.amount),
+            AssetInfo::Cw20(contract_addr) => {
+                let res: BalanceResponse = querier.query_wasm_smart(
+                    contract_addr,
+                    &Cw20QueryMsg::Balance {
+                        address: address.to_string(),
+                    },
+                )?;
+                Ok(res.balance)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for AssetInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetInfo::Native(denom) => write!(f, "{}", denom),
+            AssetInfo::Cw20(contract_addr) => write!(f, "{}", contract_addr),
+        }
+    }
 }
 
+/// Packed EVM `Timelocks` word (see `unite_timelocks`), a decimal `uint256` in JSON
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct Timelocks(pub Uint256);
+
 impl Timelocks {
+    /// Stage offsets in `unite_timelocks::Stage` order, not deployed yet
+    pub fn new(offsets: [u32; 7]) -> Self {
+        PackedTimelocks::encode(offsets, 0).into()
+    }
+    
+    pub fn packed(&self) -> PackedTimelocks {
+        PackedTimelocks::from_be_bytes(self.0.to_be_bytes())
+    }
+    
+    /// Like the EVM, the deployment time is kept in 32 bits
     pub fn set_deployed_at(&mut self, deployed_at: u64) {
-        self.deployed_at = Some(deployed_at);
+        *self = self.packed().with_deployed_at(deployed_at as u32).into();
     }
     
     pub fn get_deployed_at(&self) -> u64 {
-        self.deployed_at.unwrap_or(0)
+        self.packed().deployed_at().into()
+    }
+    
+    /// Stage the escrow is in at `now`. Destination escrows have no public cancellation.
+    pub fn stage(&self, escrow_type: &EscrowType, now: u64) -> Stage {
+        let timelocks = self.packed();
+        let (withdrawal, public_withdrawal, cancellation, public_cancellation) = match escrow_type {
+            EscrowType::Source => (
+                TimelockStage::SrcWithdrawal,
+                TimelockStage::SrcPublicWithdrawal,
+                TimelockStage::SrcCancellation,
+                Some(TimelockStage::SrcPublicCancellation),
+            ),
+            EscrowType::Destination => (
+                TimelockStage::DstWithdrawal,
+                TimelockStage::DstPublicWithdrawal,
+                TimelockStage::DstCancellation,
+                None,
+            ),
+        };
+        
+        if public_cancellation.is_some_and(|stage| now >= timelocks.get(stage)) {
+            Stage::PublicCancellation
+        } else if now >= timelocks.get(cancellation) {
+            Stage::PrivateCancellation
+        } else if now >= timelocks.get(public_withdrawal) {
+            Stage::PublicWithdrawal
+        } else if now >= timelocks.get(withdrawal) {
+            Stage::PrivateWithdrawal
+        } else {
+            Stage::FinalityLock
+        }
+    }
+}
+
+impl From<PackedTimelocks> for Timelocks {
+    fn from(timelocks: PackedTimelocks) -> Self {
+        Timelocks(Uint256::from_be_bytes(timelocks.to_be_bytes()))
     }
 }
 
@@ -42,6 +170,22 @@ pub enum EscrowType {
     Destination,
 }
 
+/// Timelock stages of an escrow, in order
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub enum Stage {
+    /// Waiting for the source chain deposit to be final, nothing can happen
+    FinalityLock,
+    /// Only the taker and resolvers can withdraw
+    PrivateWithdrawal,
+    /// Anyone can withdraw with the secret and earns a caller reward
+    PublicWithdrawal,
+    /// The maker (source), taker and resolvers can cancel
+    PrivateCancellation,
+    /// Anyone can cancel
+    PublicCancellation,
+}
+
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub enum State {
     Active,
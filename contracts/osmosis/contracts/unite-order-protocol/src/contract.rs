@@ -1,13 +1,15 @@
 use cosmwasm_std::{
     entry_point, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
-    StdError, Addr, Uint128, Timestamp,
+    Addr, Uint128, Timestamp,
 };
 use cw2::set_contract_version;
-use sha2::{Digest, Sha256};
+use unite_order::{
+    order_digest, order_hash_hex, verify_order_signature, Eip712Domain, OrderSignature,
+};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, OrderResponse, OrderHashResponse};
-use crate::state::{ORDERS, NONCES, FILLED_AMOUNTS, ESCROW_ADDRESSES, Config, CONFIG};
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, FillOrderResponse, OrderResponse, OrderHashResponse};
+use crate::state::{ORDERS, NONCES, FILLED_AMOUNTS, ESCROW_ADDRESSES, RESOLVERS, Config, CONFIG};
 use crate::types::{Order, OrderStatus};
 
 const CONTRACT_NAME: &str = "crates.io:unite-order-protocol";
@@ -42,7 +44,7 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::CreateOrder { order, signature } => {
-            execute_create_order(deps, env, info, order, signature)
+            execute_create_order(deps, env, info, *order, signature)
         }
         ExecuteMsg::FillOrder { 
             order_hash, 
@@ -58,6 +60,12 @@ pub fn execute(
         ExecuteMsg::SetEscrowFactory { address } => {
             execute_set_escrow_factory(deps, info, address)
         }
+        ExecuteMsg::AddResolver { resolver } => {
+            execute_set_resolver(deps, info, resolver, true)
+        }
+        ExecuteMsg::RemoveResolver { resolver } => {
+            execute_set_resolver(deps, info, resolver, false)
+        }
     }
 }
 
@@ -66,8 +74,12 @@ pub fn execute_create_order(
     env: Env,
     _info: MessageInfo,
     order: Order,
-    _signature: String,
+    signature: OrderSignature,
 ) -> Result<Response, ContractError> {
+    if !verify_order_signature(deps.api, &order, &signature) {
+        return Err(ContractError::InvalidSignature {});
+    }
+    
     if env.block.time >= Timestamp::from_seconds(order.deadline) {
         return Err(ContractError::OrderExpired {});
     }
@@ -78,7 +90,11 @@ pub fn execute_create_order(
         return Err(ContractError::InvalidNonce {});
     }
     
-    let order_hash = calculate_order_hash(&order)?;
+    // Replaying the order would reset its filled amount
+    let order_hash = calculate_order_hash(&order);
+    if ORDERS.has(deps.storage, order_hash.clone()) {
+        return Err(ContractError::OrderExists {});
+    }
     ORDERS.save(deps.storage, order_hash.clone(), &order)?;
     FILLED_AMOUNTS.save(deps.storage, order_hash.clone(), &Uint128::zero())?;
     
@@ -97,6 +113,11 @@ pub fn execute_fill_order(
     _taking_amount: Uint128,
     target: Option<Addr>,
 ) -> Result<Response, ContractError> {
+    // Only registered resolvers fill orders
+    if !RESOLVERS.has(deps.storage, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    
     let order = ORDERS.load(deps.storage, order_hash.clone())?;
     
     if env.block.time >= Timestamp::from_seconds(order.deadline) {
@@ -132,6 +153,10 @@ pub fn execute_fill_order(
     }
     
     Ok(Response::new()
+        .set_data(to_json_binary(&FillOrderResponse {
+            order_hash: order_hash.clone(),
+            making_amount: actual_making_amount,
+        })?)
         .add_attribute("method", "fill_order")
         .add_attribute("order_hash", order_hash)
         .add_attribute("making_amount", actual_making_amount))
@@ -175,13 +200,38 @@ pub fn execute_set_escrow_factory(
         .add_attribute("factory", address))
 }
 
+pub fn execute_set_resolver(
+    deps: DepsMut,
+    info: MessageInfo,
+    resolver: Addr,
+    allowed: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    
+    if config.admin != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    
+    if allowed {
+        RESOLVERS.save(deps.storage, &resolver, &true)?;
+    } else {
+        RESOLVERS.remove(deps.storage, &resolver);
+    }
+    
+    Ok(Response::new()
+        .add_attribute("method", if allowed { "add_resolver" } else { "remove_resolver" })
+        .add_attribute("resolver", resolver))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetOrder { order_hash } => to_json_binary(&query_order(deps, order_hash)?),
-        QueryMsg::GetOrderHash { order } => to_json_binary(&query_order_hash(order)?),
+        QueryMsg::GetOrderHash { order } => to_json_binary(&query_order_hash(*order)?),
+        QueryMsg::GetOrderDigest { order, domain } => to_json_binary(&query_order_digest(*order, domain)?),
         QueryMsg::GetFilledAmount { order_hash } => to_json_binary(&query_filled_amount(deps, order_hash)?),
         QueryMsg::GetNonce { maker } => to_json_binary(&query_nonce(deps, maker)?),
+        QueryMsg::IsResolver { resolver } => to_json_binary(&RESOLVERS.has(deps.storage, &resolver)),
     }
 }
 
@@ -205,8 +255,15 @@ fn query_order(deps: Deps, order_hash: String) -> StdResult<OrderResponse> {
 }
 
 fn query_order_hash(order: Order) -> StdResult<OrderHashResponse> {
-    let hash = calculate_order_hash(&order).map_err(|e| StdError::generic_err(e.to_string()))?;
-    Ok(OrderHashResponse { hash })
+    Ok(OrderHashResponse {
+        hash: calculate_order_hash(&order),
+    })
+}
+
+fn query_order_digest(order: Order, domain: Eip712Domain) -> StdResult<OrderHashResponse> {
+    Ok(OrderHashResponse {
+        hash: hex::encode(order_digest(&domain, &order)),
+    })
 }
 
 fn query_filled_amount(deps: Deps, order_hash: String) -> StdResult<Uint128> {
@@ -217,8 +274,6 @@ fn query_nonce(deps: Deps, maker: Addr) -> StdResult<Uint128> {
     Ok(NONCES.may_load(deps.storage, &maker)?.unwrap_or(Uint128::zero()))
 }
 
-fn calculate_order_hash(order: &Order) -> Result<String, ContractError> {
-    let order_bytes = serde_json::to_vec(order)?;
-    let hash = Sha256::digest(&order_bytes);
-    Ok(format!("{:x}", hash))
+fn calculate_order_hash(order: &Order) -> String {
+    order_hash_hex(order)
 }
@@ -9,18 +9,21 @@ pub enum ContractError {
     #[error("Overflow error: {0}")]
     Overflow(#[from] OverflowError),
 
-    #[error("Serialization error: {0}")]
-    SerdeJson(#[from] serde_json::Error),
-
     #[error("Order expired")]
     OrderExpired {},
 
     #[error("Invalid nonce")]
     InvalidNonce {},
 
+    #[error("Order already exists")]
+    OrderExists {},
+
     #[error("Order fully filled")]
     OrderFullyFilled {},
 
+    #[error("Invalid signature")]
+    InvalidSignature {},
+
     #[error("Unauthorized")]
     Unauthorized {},
 }
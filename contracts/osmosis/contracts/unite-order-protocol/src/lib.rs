@@ -3,6 +3,8 @@ pub mod error;
 pub mod msg;
 pub mod state;
 pub mod types;
-pub mod order_hash;
+
+#[cfg(test)]
+mod tests;
 
 pub use crate::error::ContractError;
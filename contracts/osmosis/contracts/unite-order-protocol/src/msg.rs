@@ -2,6 +2,7 @@ use cosmwasm_std::{Addr, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use crate::types::{Order, OrderStatus};
+use unite_order::{Eip712Domain, OrderSignature};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {}
@@ -10,8 +11,8 @@ pub struct InstantiateMsg {}
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     CreateOrder {
-        order: Order,
-        signature: String,
+        order: Box<Order>,
+        signature: OrderSignature,
     },
     FillOrder {
         order_hash: String,
@@ -25,15 +26,27 @@ pub enum ExecuteMsg {
     SetEscrowFactory {
         address: Addr,
     },
+    /// Allows `resolver` to fill orders (admin only)
+    AddResolver {
+        resolver: Addr,
+    },
+    /// Revokes `resolver` (admin only)
+    RemoveResolver {
+        resolver: Addr,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetOrder { order_hash: String },
-    GetOrderHash { order: Order },
+    GetOrderHash { order: Box<Order> },
+    /// EIP-712 digest of `order` under `domain`, as signed by EVM wallets
+    GetOrderDigest { order: Box<Order>, domain: Eip712Domain },
     GetFilledAmount { order_hash: String },
     GetNonce { maker: Addr },
+    /// Whether `resolver` may fill orders
+    IsResolver { resolver: Addr },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -43,6 +56,14 @@ pub struct OrderResponse {
     pub status: OrderStatus,
 }
 
+/// Data of a `FillOrder` response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FillOrderResponse {
+    pub order_hash: String,
+    /// Amount actually filled, capped at what was left of the order
+    pub making_amount: Uint128,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct OrderHashResponse {
     pub hash: String,
@@ -14,3 +14,5 @@ pub const ORDERS: Map<String, Order> = Map::new("orders");
 pub const NONCES: Map<&Addr, Uint128> = Map::new("nonces");
 pub const FILLED_AMOUNTS: Map<String, Uint128> = Map::new("filled_amounts");
 pub const ESCROW_ADDRESSES: Map<String, Addr> = Map::new("escrow_addresses");
+// resolvers allowed to fill orders
+pub const RESOLVERS: Map<&Addr, bool> = Map::new("resolvers");
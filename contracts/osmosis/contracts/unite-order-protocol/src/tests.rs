@@ -0,0 +1,241 @@
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::Timestamp;
+use cosmwasm_std::{from_json, Addr, Binary, Uint128};
+use unite_order::{AuctionDetails, Eip712Domain, OrderSignature};
+
+use crate::contract::{execute, instantiate, query};
+use crate::msg::{ExecuteMsg, FillOrderResponse, InstantiateMsg, OrderResponse, QueryMsg};
+use crate::types::Order;
+use crate::ContractError;
+
+// Signed by the key 0x1111..11 with `eth_signTypedData_v4`
+const MAKER: &str = "0x19E7E376E7C213B7E7e7e46cc70A5dD086DAff2A";
+const ORDER_HASH: &str = "3ff20e0af1c342cbb3bdca5369b004636fb84137ad548ec3a72e7f3af1aeab46";
+const EIP712_SIGNATURE: &str = "d636f07c02aaa9fd6bfb75c396f018e96f7534a4da2829f51bac3d7f3c78c33a075f9fe0f0b6b96f70434ec57d2ebdc1f88a05d017817bedaa1890bf21e8ab081c";
+
+fn order() -> Order {
+    Order {
+        salt: Uint128::new(42),
+        maker: Addr::unchecked(MAKER),
+        receiver: None,
+        maker_asset: "uosmo".to_string(),
+        taker_asset: "0x6B175474E89094C44Da98b954EedeAC495271d0F".to_string(),
+        making_amount: Uint128::new(1_000_000),
+        taking_amount: Uint128::new(1_000_000),
+        deadline: 1_700_003_600,
+        nonce: Uint128::zero(),
+        src_chain_id: 5555,
+        dst_chain_id: 84532,
+        auction_start_time: 1_700_000_000,
+        auction_end_time: 1_700_000_300,
+        start_price: Uint128::new(1_000_000_000_000_000_000),
+        end_price: Uint128::new(1_000_000_000_000_000_000),
+        auction_details: AuctionDetails::Linear,
+    }
+}
+
+fn signature() -> OrderSignature {
+    OrderSignature::Eip712 {
+        domain: Eip712Domain::unite(84532, "0x734F3DDcE982B1b966B4234C53a1365D44980692"),
+        signature: Binary::from(hex::decode(EIP712_SIGNATURE).unwrap()),
+    }
+}
+
+#[test]
+fn create_order_verifies_maker_signature() {
+    let mut deps = mock_dependencies();
+    instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), InstantiateMsg {}).unwrap();
+
+    let mut tampered = order();
+    tampered.making_amount = Uint128::new(2_000_000);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("relayer", &[]),
+        ExecuteMsg::CreateOrder {
+            order: Box::new(tampered),
+            signature: signature(),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidSignature {}));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("relayer", &[]),
+        ExecuteMsg::CreateOrder {
+            order: Box::new(order()),
+            signature: signature(),
+        },
+    )
+    .unwrap();
+    let res: OrderResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetOrder {
+                order_hash: ORDER_HASH.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.order, order());
+}
+
+#[test]
+fn fill_order_caps_at_remaining_and_returns_making_amount() {
+    let mut deps = mock_dependencies();
+    instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), InstantiateMsg {}).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        ExecuteMsg::AddResolver { resolver: Addr::unchecked("resolver") },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("relayer", &[]),
+        ExecuteMsg::CreateOrder {
+            order: Box::new(order()),
+            signature: signature(),
+        },
+    )
+    .unwrap();
+
+    let mut fill = |making_amount: u128| {
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(1_700_000_000);
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("resolver", &[]),
+            ExecuteMsg::FillOrder {
+                order_hash: ORDER_HASH.to_string(),
+                making_amount: Uint128::new(making_amount),
+                taking_amount: Uint128::new(making_amount),
+                target: None,
+            },
+        )
+    };
+
+    let res = fill(600_000).unwrap();
+    let data: FillOrderResponse = from_json(res.data.unwrap()).unwrap();
+    assert_eq!(data.making_amount, Uint128::new(600_000));
+
+    // Only 400_000 is left
+    let res = fill(600_000).unwrap();
+    let data: FillOrderResponse = from_json(res.data.unwrap()).unwrap();
+    assert_eq!(data, FillOrderResponse { order_hash: ORDER_HASH.to_string(), making_amount: Uint128::new(400_000) });
+
+    let err = fill(1).unwrap_err();
+    assert!(matches!(err, ContractError::OrderFullyFilled {}));
+    let nonce: Uint128 = from_json(
+        query(deps.as_ref(), mock_env(), QueryMsg::GetNonce { maker: Addr::unchecked(MAKER) }).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(nonce, Uint128::one());
+}
+
+#[test]
+fn only_registered_resolvers_fill_orders() {
+    let mut deps = mock_dependencies();
+    instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), InstantiateMsg {}).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("relayer", &[]),
+        ExecuteMsg::CreateOrder {
+            order: Box::new(order()),
+            signature: signature(),
+        },
+    )
+    .unwrap();
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(1_700_000_000);
+    let fill = ExecuteMsg::FillOrder {
+        order_hash: ORDER_HASH.to_string(),
+        making_amount: Uint128::new(1_000_000),
+        taking_amount: Uint128::new(1_000_000),
+        target: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), mock_info("stranger", &[]), fill.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    // Only the admin registers resolvers
+    let add = ExecuteMsg::AddResolver { resolver: Addr::unchecked("stranger") };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("stranger", &[]), add.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), add).unwrap();
+    execute(deps.as_mut(), env.clone(), mock_info("stranger", &[]), fill.clone()).unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        ExecuteMsg::RemoveResolver { resolver: Addr::unchecked("stranger") },
+    )
+    .unwrap();
+    let is_resolver: bool = from_json(
+        query(deps.as_ref(), mock_env(), QueryMsg::IsResolver { resolver: Addr::unchecked("stranger") }).unwrap(),
+    )
+    .unwrap();
+    assert!(!is_resolver);
+}
+
+#[test]
+fn replayed_create_order_cannot_reopen_an_order() {
+    let mut deps = mock_dependencies();
+    instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), InstantiateMsg {}).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        ExecuteMsg::AddResolver { resolver: Addr::unchecked("resolver") },
+    )
+    .unwrap();
+    let create = ExecuteMsg::CreateOrder {
+        order: Box::new(order()),
+        signature: signature(),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("relayer", &[]), create.clone()).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(1_700_000_000);
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("resolver", &[]),
+        ExecuteMsg::FillOrder {
+            order_hash: ORDER_HASH.to_string(),
+            making_amount: Uint128::new(600_000),
+            taking_amount: Uint128::new(600_000),
+            target: None,
+        },
+    )
+    .unwrap();
+    let filled = |deps: &cosmwasm_std::OwnedDeps<_, _, _>| -> Uint128 {
+        from_json(
+            query(deps.as_ref(), mock_env(), QueryMsg::GetFilledAmount { order_hash: ORDER_HASH.to_string() }).unwrap(),
+        )
+        .unwrap()
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("relayer", &[]), create.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::OrderExists {}));
+    assert_eq!(filled(&deps), Uint128::new(600_000));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MAKER, &[]),
+        ExecuteMsg::CancelOrder { order_hash: ORDER_HASH.to_string() },
+    )
+    .unwrap();
+    let err = execute(deps.as_mut(), mock_env(), mock_info("relayer", &[]), create).unwrap_err();
+    assert!(matches!(err, ContractError::OrderExists {}));
+    assert_eq!(filled(&deps), Uint128::new(1_000_000));
+}
@@ -1,19 +1,28 @@
 use cosmwasm_std::{
     entry_point, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
-    Addr, Uint128, WasmMsg, CosmosMsg, Coin, Reply,
+    Addr, Uint128, WasmMsg, CosmosMsg, Coin, Reply, Storage, SubMsg, SubMsgResponse, from_json,
 };
 use cw2::set_contract_version;
-use sha2::{Digest, Sha256};
+use cw_utils::parse_execute_response_data;
+use unite_order::{order_hash_hex, verify_order_signature, OrderSignature};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ConfigResponse};
-use crate::state::{CONFIG, ORDER_FILLS, ESCROW_ADDRESSES, Config};
-use crate::types::{Order, Immutables};
+use crate::msg::{
+    ExecuteMsg, InstantiateMsg, QueryMsg, ConfigResponse, EscrowAddressResponse, TokenDecimalsResponse,
+};
+use crate::state::{
+    CONFIG, ESCROW_ADDRESSES, PENDING_FILL, PENDING_SRC_FILL, TOKEN_DECIMALS, Config, PendingFill,
+    PendingSrcFill, escrow_type_key,
+};
+use crate::types::{AssetInfo, Order, Immutables};
+use crate::decimals::{self, CANONICAL_DECIMALS};
 use crate::dutch_auction::DutchAuction;
 
 const CONTRACT_NAME: &str = "crates.io:unite-resolver";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 const INSTANTIATE_ESCROW_REPLY_ID: u64 = 1;
+const FILL_ORDER_REPLY_ID: u64 = 2;
+const FILL_SRC_ORDER_REPLY_ID: u64 = 3;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -28,9 +37,14 @@ pub fn instantiate(
         owner: info.sender,
         factory: msg.factory,
         order_protocol: msg.order_protocol,
+        safety_deposit_denom: msg.safety_deposit_denom,
     };
     CONFIG.save(deps.storage, &config)?;
     
+    for token in msg.token_decimals {
+        save_token_decimals(deps.storage, token.chain_id, &token.asset, token.decimals)?;
+    }
+    
     Ok(Response::new().add_attribute("method", "instantiate"))
 }
 
@@ -58,11 +72,14 @@ pub fn execute(
         ExecuteMsg::FillOrder { immutables, order, src_cancellation_timestamp, src_amount } => {
             execute_fill_order(deps, env, info, immutables, order, src_cancellation_timestamp, src_amount)
         }
+        ExecuteMsg::SetTokenDecimals { chain_id, asset, decimals } => {
+            execute_set_token_decimals(deps, info, chain_id, asset, decimals)
+        }
         ExecuteMsg::ApproveToken { token, amount } => {
             execute_approve_token(deps, env, info, token, amount)
         }
-        ExecuteMsg::Withdraw { escrow, secret, immutables } => {
-            execute_withdraw(deps, env, info, escrow, secret, immutables)
+        ExecuteMsg::Withdraw { escrow, secret, immutables, index, proof } => {
+            execute_withdraw(deps, env, info, escrow, secret, immutables, index, proof)
         }
         ExecuteMsg::Cancel { escrow, immutables } => {
             execute_cancel(deps, env, info, escrow, immutables)
@@ -70,13 +87,14 @@ pub fn execute(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_deploy_src(
     deps: DepsMut,
-    env: Env,
+    _env: Env,
     info: MessageInfo,
     immutables: Immutables,
     order: Order,
-    _signature: String,
+    signature: OrderSignature,
     _amount: Uint128,
     partial_amount: Uint128,
 ) -> Result<Response, ContractError> {
@@ -87,61 +105,80 @@ pub fn execute_deploy_src(
         return Err(ContractError::Unauthorized {});
     }
     
+    if !verify_order_signature(deps.api, &order, &signature) {
+        return Err(ContractError::InvalidSignature {});
+    }
+    
     // Calculate order hash
     let order_hash = calculate_order_hash(&order);
+    validate_src(&order, &order_hash, &immutables)?;
     
-    // Check if this is the first fill for this order
-    let existing_escrow = ESCROW_ADDRESSES.may_load(deps.storage, order_hash.clone())?;
+    let safety_deposit = must_pay_safety_deposit(&info, &config.safety_deposit_denom)?;
     
-    let safety_deposit = info.funds.iter()
-        .find(|coin| coin.denom == "uosmo")
-        .map(|coin| coin.amount)
-        .unwrap_or(Uint128::zero());
-    
-    if safety_deposit.is_zero() {
-        return Err(ContractError::NoSafetyDeposit {});
-    }
-    
-    let mut messages = vec![];
-    
-    if let Some(escrow_address) = existing_escrow {
-        // Subsequent resolver - add to existing escrow
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: escrow_address.to_string(),
-            msg: to_json_binary(&EscrowExecuteMsg::AddResolverSafetyDeposit {
-                resolver: info.sender.clone(),
-                partial_amount,
-            })?,
-            funds: info.funds,
-        }));
-    } else {
-        // First resolver - create new escrow through factory
-        let create_msg = to_json_binary(&FactoryExecuteMsg::CreateSrcEscrowPartialFor {
-            immutables: immutables.clone(),
-            partial_amount,
-            resolver: info.sender.clone(),
-        })?;
-        
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: config.factory.to_string(),
-            msg: create_msg,
-            funds: info.funds,
-        }));
-        
-        // TODO: We need to capture the escrow address from the factory response
-        // For now, we'll assume the factory handles this correctly
-    }
+    // The order protocol tracks fills and may fill less than requested; the source escrow
+    // is deployed or joined for the actual amount once it replies
+    PENDING_SRC_FILL.save(deps.storage, &PendingSrcFill {
+        immutables,
+        resolver: info.sender,
+        safety_deposit,
+    })?;
     
-    // TODO: Handle order filling through order protocol
-    // This would require interaction with the order protocol contract
+    let fill_msg = to_json_binary(&OrderProtocolExecuteMsg::FillOrder {
+        order_hash: order_hash.clone(),
+        making_amount: partial_amount,
+        taking_amount: partial_amount.multiply_ratio(order.taking_amount, order.making_amount),
+        target: None,
+    })?;
     
     Ok(Response::new()
-        .add_messages(messages)
+        .add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: config.order_protocol.to_string(),
+                msg: fill_msg,
+                funds: vec![],
+            },
+            FILL_SRC_ORDER_REPLY_ID,
+        ))
         .add_attribute("method", "deploy_src")
         .add_attribute("order_hash", order_hash)
         .add_attribute("partial_amount", partial_amount.to_string()))
 }
 
+/// Sends the source fill the order protocol recorded to the factory, which deploys the escrow
+/// on the first fill and adds later resolvers to it
+fn reply_fill_src_order(deps: DepsMut, res: SubMsgResponse) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let pending = PENDING_SRC_FILL.load(deps.storage)?;
+    PENDING_SRC_FILL.remove(deps.storage);
+    
+    let filled = parse_fill_order_response(&res)?;
+    if filled.order_hash != pending.immutables.order_hash {
+        return Err(ContractError::OrderHashMismatch {});
+    }
+    
+    let create_msg = to_json_binary(&FactoryExecuteMsg::CreateSrcEscrowPartialFor {
+        immutables: pending.immutables,
+        partial_amount: filled.making_amount,
+        resolver: pending.resolver,
+    })?;
+    
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: config.factory.to_string(),
+                msg: create_msg,
+                funds: vec![Coin {
+                    denom: config.safety_deposit_denom,
+                    amount: pending.safety_deposit,
+                }],
+            },
+            INSTANTIATE_ESCROW_REPLY_ID,
+        ))
+        .add_attribute("method", "reply_fill_src_order")
+        .add_attribute("order_hash", filled.order_hash)
+        .add_attribute("making_amount", filled.making_amount.to_string()))
+}
+
 pub fn execute_deploy_dst(
     deps: DepsMut,
     _env: Env,
@@ -152,15 +189,14 @@ pub fn execute_deploy_dst(
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     
-    let safety_deposit = info.funds.iter()
-        .find(|coin| coin.denom == "uosmo")
-        .map(|coin| coin.amount)
-        .unwrap_or(Uint128::zero());
-    
-    if safety_deposit.is_zero() {
-        return Err(ContractError::NoSafetyDeposit {});
+    // The resolver locks native destination tokens through the escrow's `Fund` message; this
+    // contract holds no CW20 tokens to send to the escrow on its behalf
+    if let AssetInfo::Cw20(_) = &immutables.token {
+        return Err(ContractError::Cw20NotSupported {});
     }
     
+    let safety_deposit = must_pay_safety_deposit(&info, &config.safety_deposit_denom)?;
+    
     // Deploy destination escrow through factory
     let create_msg = to_json_binary(&FactoryExecuteMsg::CreateDstEscrowPartialFor {
         immutables: immutables.clone(),
@@ -169,29 +205,20 @@ pub fn execute_deploy_dst(
         resolver: info.sender.clone(),
     })?;
     
-    let mut messages = vec![];
-    
-    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: config.factory.to_string(),
-        msg: create_msg,
-        funds: vec![Coin {
-            denom: "uosmo".to_string(),
-            amount: safety_deposit,
-        }],
-    }));
-    
-    // Transfer destination tokens to escrow
-    // For native tokens (uosmo), we would send them directly
-    // For CW20 tokens, we would need a different approach
-    if immutables.token == "uosmo" {
-        // Native token transfer would be handled differently
-    } else {
-        // CW20 token transfer
-        // TODO: Implement CW20 transfer
-    }
+    let messages = vec![SubMsg::reply_on_success(
+        WasmMsg::Execute {
+            contract_addr: config.factory.to_string(),
+            msg: create_msg,
+            funds: vec![Coin {
+                denom: config.safety_deposit_denom.clone(),
+                amount: safety_deposit,
+            }],
+        },
+        INSTANTIATE_ESCROW_REPLY_ID,
+    )];
     
     Ok(Response::new()
-        .add_messages(messages)
+        .add_submessages(messages)
         .add_attribute("method", "deploy_dst")
         .add_attribute("order_hash", immutables.order_hash)
         .add_attribute("partial_amount", partial_amount.to_string()))
@@ -214,77 +241,203 @@ pub fn execute_fill_order(
     
     // Calculate order hash
     let order_hash = calculate_order_hash(&order);
+    let current_time = env.block.time.seconds();
+    validate_fill(&order, &order_hash, &immutables, src_cancellation_timestamp, current_time)?;
     
-    // Check if order is already completed
-    let filled_amount = ORDER_FILLS.may_load(deps.storage, order_hash.clone())?.unwrap_or(0);
-    if filled_amount >= order.making_amount.u128() {
-        return Err(ContractError::OrderCompleted {});
-    }
+    // Quote the requested amount up front so bad prices or decimals fail before the fill
+    let quote = quote_fill(deps.as_ref(), &order, src_amount, current_time)?;
     
-    // Check remaining amount
-    let remaining_amount = order.making_amount.checked_sub(Uint128::from(filled_amount))?;
-    if src_amount > remaining_amount {
-        return Err(ContractError::InvalidSrcAmount {});
+    // The escrow must be able to hold what this fill owes the maker
+    if immutables.amount < quote.taking.amount {
+        return Err(ContractError::AmountMismatch {});
     }
     
-    // Calculate destination amount based on current Dutch auction price
-    let current_time = env.block.time.seconds();
-    let dest_amount = DutchAuction::calculate_taking_amount(
-        src_amount,
-        order.start_price,
-        order.end_price,
-        order.auction_start_time,
-        order.auction_end_time,
-        current_time,
-    )?;
+    let safety_deposit = must_pay_safety_deposit(&info, &config.safety_deposit_denom)?;
     
-    let safety_deposit = info.funds.iter()
-        .find(|coin| coin.denom == "uosmo")
-        .map(|coin| coin.amount)
-        .unwrap_or(Uint128::zero());
+    // The order protocol tracks fills and may fill less than requested; the destination
+    // escrow is deployed for the actual amount once it replies
+    PENDING_FILL.save(deps.storage, &PendingFill {
+        immutables,
+        order,
+        src_cancellation_timestamp,
+        resolver: info.sender,
+        safety_deposit,
+    })?;
     
-    if safety_deposit.is_zero() {
-        return Err(ContractError::NoSafetyDeposit {});
+    let fill_msg = to_json_binary(&OrderProtocolExecuteMsg::FillOrder {
+        order_hash: order_hash.clone(),
+        making_amount: src_amount,
+        taking_amount: quote.taking.amount,
+        target: None,
+    })?;
+    
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: config.order_protocol.to_string(),
+                msg: fill_msg,
+                funds: vec![],
+            },
+            FILL_ORDER_REPLY_ID,
+        ))
+        .add_attribute("method", "fill_order")
+        .add_attribute("order_hash", order_hash)
+        .add_attribute("src_amount", src_amount.to_string()))
+}
+
+/// Deploys the destination escrow for the amount the order protocol filled
+fn reply_fill_order(deps: DepsMut, env: Env, res: SubMsgResponse) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let pending = PENDING_FILL.load(deps.storage)?;
+    PENDING_FILL.remove(deps.storage);
+    
+    let filled = parse_fill_order_response(&res)?;
+    let order_hash = calculate_order_hash(&pending.order);
+    if filled.order_hash != order_hash {
+        return Err(ContractError::OrderHashMismatch {});
     }
     
+    // Calculate destination amount based on current Dutch auction price
+    let current_time = env.block.time.seconds();
+    let quote = quote_fill(deps.as_ref(), &pending.order, filled.making_amount, current_time)?;
+    let dest_amount = quote.taking.amount;
+    
     // Deploy destination escrow with calculated amount
     let create_msg = to_json_binary(&FactoryExecuteMsg::CreateDstEscrowPartialFor {
-        immutables: immutables.clone(),
-        src_cancellation_timestamp,
+        immutables: pending.immutables,
+        src_cancellation_timestamp: pending.src_cancellation_timestamp,
         partial_amount: dest_amount,
-        resolver: info.sender.clone(),
+        resolver: pending.resolver,
     })?;
     
-    let mut messages = vec![];
-    
-    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: config.factory.to_string(),
-        msg: create_msg,
-        funds: vec![Coin {
-            denom: "uosmo".to_string(),
-            amount: safety_deposit,
-        }],
-    }));
-    
-    // Update fill tracking
-    ORDER_FILLS.save(deps.storage, order_hash.clone(), &(filled_amount + src_amount.u128()))?;
-    
-    // Get current price for event
-    let current_price = DutchAuction::get_current_price(
-        order.start_price,
-        order.end_price,
-        order.auction_start_time,
-        order.auction_end_time,
-        current_time,
-    )?;
+    let messages = vec![SubMsg::reply_on_success(
+        WasmMsg::Execute {
+            contract_addr: config.factory.to_string(),
+            msg: create_msg,
+            funds: vec![Coin {
+                denom: config.safety_deposit_denom.clone(),
+                amount: pending.safety_deposit,
+            }],
+        },
+        INSTANTIATE_ESCROW_REPLY_ID,
+    )];
     
     Ok(Response::new()
-        .add_messages(messages)
-        .add_attribute("method", "fill_order")
+        .add_submessages(messages)
+        .add_attribute("method", "reply_fill_order")
         .add_attribute("order_hash", order_hash)
-        .add_attribute("src_amount", src_amount.to_string())
+        .add_attribute("making_amount", filled.making_amount.to_string())
         .add_attribute("dest_amount", dest_amount.to_string())
-        .add_attribute("current_price", current_price.to_string()))
+        .add_attribute("current_price", quote.current_price.to_string())
+        .add_attribute("src_decimals", quote.src_decimals.to_string())
+        .add_attribute("dst_decimals", quote.dst_decimals.to_string())
+        .add_attribute("rounding_dust", quote.taking.dust.to_string()))
+}
+
+struct FillQuote {
+    current_price: Uint128,
+    src_decimals: u8,
+    dst_decimals: u8,
+    taking: decimals::TakingAmount,
+}
+
+/// Prices `making_amount` at the current auction price, converted from the maker asset's
+/// decimals on the source chain to the taker asset's on the destination
+fn quote_fill(
+    deps: Deps,
+    order: &Order,
+    making_amount: Uint128,
+    current_time: u64,
+) -> Result<FillQuote, ContractError> {
+    let src_decimals = load_token_decimals(deps.storage, order.src_chain_id, &order.maker_asset)?;
+    let dst_decimals = load_token_decimals(deps.storage, order.dst_chain_id, &order.taker_asset)?;
+    let current_price = DutchAuction::get_current_price(order, current_time)?;
+    let taking = decimals::taking_amount(making_amount, src_decimals, current_price, dst_decimals)?;
+    Ok(FillQuote { current_price, src_decimals, dst_decimals, taking })
+}
+
+/// Checks source escrow immutables against the signed order: the escrow must lock the
+/// maker's whole making amount of the maker asset
+fn validate_src(order: &Order, order_hash: &str, immutables: &Immutables) -> Result<(), ContractError> {
+    if immutables.order_hash != order_hash {
+        return Err(ContractError::OrderHashMismatch {});
+    }
+    
+    if immutables.maker != order.maker {
+        return Err(ContractError::MakerMismatch {});
+    }
+    
+    if !immutables.token.is(&order.maker_asset) {
+        return Err(ContractError::AssetMismatch {});
+    }
+    
+    if immutables.amount != order.making_amount {
+        return Err(ContractError::AmountMismatch {});
+    }
+    
+    Ok(())
+}
+
+/// Checks a fill against the order: the destination escrow must be for this order and pay
+/// its maker in the taker asset, in time, and must not outlive the source escrow
+fn validate_fill(
+    order: &Order,
+    order_hash: &str,
+    immutables: &Immutables,
+    src_cancellation_timestamp: u64,
+    current_time: u64,
+) -> Result<(), ContractError> {
+    if immutables.order_hash != order_hash {
+        return Err(ContractError::OrderHashMismatch {});
+    }
+    
+    // The destination escrow pays out to the maker's receiver, if any
+    if immutables.maker != *order.receiver.as_ref().unwrap_or(&order.maker) {
+        return Err(ContractError::MakerMismatch {});
+    }
+    
+    if !immutables.token.is(&order.taker_asset) {
+        return Err(ContractError::AssetMismatch {});
+    }
+    
+    if current_time >= order.deadline {
+        return Err(ContractError::OrderExpired {});
+    }
+    
+    if current_time < order.auction_start_time {
+        return Err(ContractError::AuctionNotStarted {});
+    }
+    
+    // As `EscrowFactory.createDstEscrow`: the escrow is deployed now and its cancellation
+    // must not come after the source escrow's
+    if immutables.timelocks.dst_cancellation(current_time) > src_cancellation_timestamp {
+        return Err(ContractError::InvalidSrcCancellationTimestamp {});
+    }
+    
+    Ok(())
+}
+
+pub fn execute_set_token_decimals(
+    deps: DepsMut,
+    info: MessageInfo,
+    chain_id: u64,
+    asset: String,
+    decimals: u8,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    
+    // Only owner can maintain the token registry
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    
+    save_token_decimals(deps.storage, chain_id, &asset, decimals)?;
+    
+    Ok(Response::new()
+        .add_attribute("method", "set_token_decimals")
+        .add_attribute("chain_id", chain_id.to_string())
+        .add_attribute("asset", asset)
+        .add_attribute("decimals", decimals.to_string()))
 }
 
 pub fn execute_approve_token(
@@ -308,6 +461,7 @@ pub fn execute_approve_token(
         .add_attribute("method", "approve_token"))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_withdraw(
     _deps: DepsMut,
     _env: Env,
@@ -315,10 +469,14 @@ pub fn execute_withdraw(
     escrow: Addr,
     secret: String,
     immutables: Immutables,
+    index: Option<u16>,
+    proof: Option<Vec<String>>,
 ) -> Result<Response, ContractError> {
     let withdraw_msg = to_json_binary(&EscrowExecuteMsg::WithdrawWithSecret {
         secret,
         immutables,
+        index,
+        proof,
     })?;
     
     let msg = CosmosMsg::Wasm(WasmMsg::Execute {
@@ -358,37 +516,53 @@ pub fn execute_cancel(
 
 // Helper function to calculate order hash
 fn calculate_order_hash(order: &Order) -> String {
-    let mut hasher = Sha256::new();
-    
-    // Create a deterministic string representation of the order
-    let order_string = format!(
-        "{}-{}-{}-{}-{}-{}-{}-{}-{}-{}-{}-{}-{}-{}-{}",
-        order.salt,
-        order.maker,
-        order.receiver,
-        order.maker_asset,
-        order.taker_asset,
-        order.making_amount,
-        order.taking_amount,
-        order.deadline,
-        order.nonce,
-        order.src_chain_id,
-        order.dst_chain_id,
-        order.auction_start_time,
-        order.auction_end_time,
-        order.start_price,
-        order.end_price
-    );
-    
-    hasher.update(order_string.as_bytes());
-    hex::encode(hasher.finalize())
+    order_hash_hex(order)
+}
+
+fn save_token_decimals(
+    storage: &mut dyn Storage,
+    chain_id: u64,
+    asset: &str,
+    decimals: u8,
+) -> Result<(), ContractError> {
+    if decimals > CANONICAL_DECIMALS {
+        return Err(ContractError::InvalidDecimals { decimals });
+    }
+    TOKEN_DECIMALS.save(storage, (chain_id, asset), &decimals)?;
+    Ok(())
+}
+
+fn load_token_decimals(storage: &dyn Storage, chain_id: u64, asset: &str) -> Result<u8, ContractError> {
+    TOKEN_DECIMALS
+        .may_load(storage, (chain_id, asset))?
+        .ok_or_else(|| ContractError::UnknownTokenDecimals { chain_id, asset: asset.to_string() })
+}
+
+/// Returns the safety deposit attached in `denom`, erroring if none was sent
+fn must_pay_safety_deposit(info: &MessageInfo, denom: &str) -> Result<Uint128, ContractError> {
+    let safety_deposit = info.funds.iter()
+        .find(|coin| coin.denom == denom)
+        .map(|coin| coin.amount)
+        .unwrap_or(Uint128::zero());
+    
+    if safety_deposit.is_zero() {
+        return Err(ContractError::NoSafetyDeposit {});
+    }
+    
+    Ok(safety_deposit)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetConfig {} => to_json_binary(&query_config(deps)?),
-        QueryMsg::GetOrderHash { order } => to_json_binary(&query_order_hash(order)?),
+        QueryMsg::GetOrderHash { order } => to_json_binary(&query_order_hash(*order)?),
+        QueryMsg::GetEscrowAddress { order_hash, is_source } => {
+            to_json_binary(&query_escrow_address(deps, order_hash, is_source)?)
+        }
+        QueryMsg::GetTokenDecimals { chain_id, asset } => {
+            to_json_binary(&query_token_decimals(deps, chain_id, asset)?)
+        }
     }
 }
 
@@ -398,6 +572,7 @@ fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         owner: config.owner,
         factory: config.factory,
         order_protocol: config.order_protocol,
+        safety_deposit_denom: config.safety_deposit_denom,
     })
 }
 
@@ -405,18 +580,117 @@ fn query_order_hash(order: Order) -> StdResult<String> {
     Ok(calculate_order_hash(&order))
 }
 
+fn query_escrow_address(deps: Deps, order_hash: String, is_source: bool) -> StdResult<EscrowAddressResponse> {
+    let escrow_address = ESCROW_ADDRESSES.may_load(
+        deps.storage,
+        (&order_hash, escrow_type_key(is_source)),
+    )?;
+    Ok(EscrowAddressResponse { escrow_address })
+}
+
+fn query_token_decimals(deps: Deps, chain_id: u64, asset: String) -> StdResult<TokenDecimalsResponse> {
+    let decimals = TOKEN_DECIMALS.may_load(deps.storage, (chain_id, &asset))?;
+    Ok(TokenDecimalsResponse { decimals })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
     match msg.id {
         INSTANTIATE_ESCROW_REPLY_ID => {
-            // Handle escrow instantiation reply
-            // Extract the escrow address and store it
-            Ok(Response::new())
+            let res = msg.result.into_result().map_err(cosmwasm_std::StdError::generic_err)?;
+            let created = parse_create_escrow_response(&res)?;
+            
+            ESCROW_ADDRESSES.save(
+                deps.storage,
+                (&created.order_hash, escrow_type_key(created.is_source)),
+                &created.escrow_address,
+            )?;
+            
+            Ok(Response::new()
+                .add_attribute("method", "reply_create_escrow")
+                .add_attribute("order_hash", created.order_hash)
+                .add_attribute("is_source", created.is_source.to_string())
+                .add_attribute("escrow_address", created.escrow_address))
+        }
+        FILL_ORDER_REPLY_ID => {
+            let res = msg.result.into_result().map_err(cosmwasm_std::StdError::generic_err)?;
+            reply_fill_order(deps, env, res)
+        }
+        FILL_SRC_ORDER_REPLY_ID => {
+            let res = msg.result.into_result().map_err(cosmwasm_std::StdError::generic_err)?;
+            reply_fill_src_order(deps, res)
         }
         _ => Err(ContractError::UnknownReplyId { id: msg.id }),
     }
 }
 
+/// Reads the escrow address from the factory's response data, falling back to its wasm event
+fn parse_create_escrow_response(res: &SubMsgResponse) -> Result<FactoryCreateEscrowResponse, ContractError> {
+    if let Some(data) = &res.data {
+        if let Ok(execute_data) = parse_execute_response_data(data) {
+            if let Some(inner) = execute_data.data {
+                if let Ok(created) = from_json::<FactoryCreateEscrowResponse>(&inner) {
+                    return Ok(created);
+                }
+            }
+        }
+    }
+    
+    res.events
+        .iter()
+        .filter(|event| event.ty == "wasm")
+        .find_map(|event| {
+            let attr = |key: &str| {
+                event.attributes.iter()
+                    .find(|attr| attr.key == key)
+                    .map(|attr| attr.value.clone())
+            };
+            let is_source = match attr("method")?.as_str() {
+                "create_src_escrow" => true,
+                "create_dst_escrow" => false,
+                _ => return None,
+            };
+            Some(FactoryCreateEscrowResponse {
+                escrow_address: Addr::unchecked(attr("escrow_address")?),
+                order_hash: attr("order_hash")?,
+                is_source,
+            })
+        })
+        .ok_or(ContractError::EscrowAddressNotFound {})
+}
+
+/// Reads the filled amount from the order protocol's response data, falling back to its wasm event
+fn parse_fill_order_response(res: &SubMsgResponse) -> Result<OrderProtocolFillOrderResponse, ContractError> {
+    if let Some(data) = &res.data {
+        if let Ok(execute_data) = parse_execute_response_data(data) {
+            if let Some(inner) = execute_data.data {
+                if let Ok(filled) = from_json::<OrderProtocolFillOrderResponse>(&inner) {
+                    return Ok(filled);
+                }
+            }
+        }
+    }
+    
+    res.events
+        .iter()
+        .filter(|event| event.ty == "wasm")
+        .find_map(|event| {
+            let attr = |key: &str| {
+                event.attributes.iter()
+                    .find(|attr| attr.key == key)
+                    .map(|attr| attr.value.clone())
+            };
+            if attr("method")? != "fill_order" {
+                return None;
+            }
+            Some(OrderProtocolFillOrderResponse {
+                order_hash: attr("order_hash")?,
+                making_amount: attr("making_amount")?.parse().ok()?,
+            })
+        })
+        .ok_or(ContractError::FilledAmountNotFound {})
+}
+
 // Message types for external contracts
 #[derive(serde::Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -428,6 +702,8 @@ pub enum EscrowExecuteMsg {
     WithdrawWithSecret {
         secret: String,
         immutables: Immutables,
+        index: Option<u16>,
+        proof: Option<Vec<String>>,
     },
     Cancel {
         immutables: Immutables,
@@ -448,4 +724,28 @@ pub enum FactoryExecuteMsg {
         partial_amount: Uint128,
         resolver: Addr,
     },
-}
\ No newline at end of file
+}
+
+#[derive(serde::Deserialize)]
+pub struct FactoryCreateEscrowResponse {
+    pub escrow_address: Addr,
+    pub order_hash: String,
+    pub is_source: bool,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderProtocolExecuteMsg {
+    FillOrder {
+        order_hash: String,
+        making_amount: Uint128,
+        taking_amount: Uint128,
+        target: Option<Addr>,
+    },
+}
+
+#[derive(serde::Deserialize)]
+pub struct OrderProtocolFillOrderResponse {
+    pub order_hash: String,
+    pub making_amount: Uint128,
+}
@@ -0,0 +1,96 @@
+use cosmwasm_std::{StdError, StdResult, Uint128, Uint256};
+
+use crate::dutch_auction::PRICE_PRECISION;
+
+/// Auction prices are quoted between whole tokens, so amounts are priced at 18 decimals
+/// whatever their token's own decimals
+pub const CANONICAL_DECIMALS: u8 = 18;
+
+/// Taking amount in the taker asset's own decimals
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TakingAmount {
+    pub amount: Uint128,
+    /// What rounding up to the taker asset's decimals added for the maker, at 18 decimals.
+    /// `amount` scaled back up less `dust` is exactly the 18-decimal taking amount.
+    pub dust: Uint128,
+}
+
+/// Converts `making_amount`, in `making_decimals`, at the 18-decimal auction `price` into the
+/// taker asset's `taking_decimals`. Every division rounds up, in the maker's favour.
+pub fn taking_amount(
+    making_amount: Uint128,
+    making_decimals: u8,
+    price: Uint128,
+    taking_decimals: u8,
+) -> StdResult<TakingAmount> {
+    // Scaling up to 18 decimals is exact
+    let making_amount = making_amount.checked_mul(scale(making_decimals)?)?;
+
+    let canonical = Uint256::from(making_amount).checked_mul(Uint256::from(price))?;
+    let canonical = Uint128::try_from(div_ceil(canonical, Uint256::from(PRICE_PRECISION)))?;
+
+    let factor = scale(taking_decimals)?;
+    let amount = Uint128::try_from(div_ceil(Uint256::from(canonical), Uint256::from(factor)))?;
+    let dust = Uint256::from(amount) * Uint256::from(factor) - Uint256::from(canonical);
+    Ok(TakingAmount { amount, dust: Uint128::try_from(dust)? })
+}
+
+/// Factor from `decimals` up to 18 decimals
+fn scale(decimals: u8) -> StdResult<Uint128> {
+    if decimals > CANONICAL_DECIMALS {
+        return Err(StdError::generic_err(format!("Unsupported token decimals: {decimals}")));
+    }
+    Ok(Uint128::new(10u128.pow(u32::from(CANONICAL_DECIMALS - decimals))))
+}
+
+fn div_ceil(numerator: Uint256, denominator: Uint256) -> Uint256 {
+    let quotient = numerator / denominator;
+    if quotient * denominator == numerator {
+        quotient
+    } else {
+        quotient + Uint256::one()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE: u128 = PRICE_PRECISION;
+
+    #[test]
+    fn converts_between_decimals() {
+        // 1.5 tokens at 18 decimals for 2 taker tokens each, paid in a 6-decimal asset
+        let taking = taking_amount(Uint128::new(1_500_000_000_000_000_000), 18, Uint128::new(2 * ONE), 6).unwrap();
+        assert_eq!(taking, TakingAmount { amount: Uint128::new(3_000_000), dust: Uint128::zero() });
+
+        // and back the other way
+        let taking = taking_amount(Uint128::new(3_000_000), 6, Uint128::new(ONE / 2), 18).unwrap();
+        assert_eq!(taking.amount, Uint128::new(1_500_000_000_000_000_000));
+        assert_eq!(taking.dust, Uint128::zero());
+    }
+
+    #[test]
+    fn rounds_up_for_the_maker_and_reports_dust() {
+        // 1.2345678 taker tokens do not fit in 6 decimals
+        let taking = taking_amount(Uint128::new(12_345_678), 7, Uint128::new(ONE), 6).unwrap();
+        assert_eq!(taking.amount, Uint128::new(1_234_568));
+        assert_eq!(taking.dust, Uint128::new(200_000_000_000));
+        // Nothing is lost: the dust accounts for all of the rounding
+        assert_eq!(
+            taking.amount.u128() * 1_000_000_000_000 - taking.dust.u128(),
+            1_234_567_800_000_000_000
+        );
+
+        // A price below one base unit still rounds up
+        let taking = taking_amount(Uint128::new(1), 18, Uint128::new(1), 18).unwrap();
+        assert_eq!(taking, TakingAmount { amount: Uint128::new(1), dust: Uint128::zero() });
+    }
+
+    #[test]
+    fn rejects_unsupported_decimals() {
+        assert!(taking_amount(Uint128::new(1), 19, Uint128::new(ONE), 6).is_err());
+        assert!(taking_amount(Uint128::new(1), 6, Uint128::new(ONE), 19).is_err());
+        assert!(taking_amount(Uint128::MAX, 6, Uint128::new(ONE), 6).is_err());
+    }
+}
@@ -1,61 +1,238 @@
 use cosmwasm_std::{StdError, StdResult, Uint128};
+use unite_auction::{Auction, Curve, Error, Point};
+use unite_order::{AuctionDetails, Order};
 
+pub use unite_auction::PRICE_PRECISION;
+
+/// CosmWasm front of the shared `unite_auction` engine
 pub struct DutchAuction;
 
 impl DutchAuction {
-    /// Calculate the current price based on Dutch auction parameters
-    pub fn get_current_price(
-        start_price: Uint128,
-        end_price: Uint128,
-        auction_start_time: u64,
-        auction_end_time: u64,
-        current_time: u64,
-    ) -> StdResult<Uint128> {
-        if current_time < auction_start_time {
-            return Err(StdError::generic_err("Auction not started"));
-        }
-        
-        if current_time >= auction_end_time {
-            return Ok(end_price);
-        }
-        
-        if auction_end_time <= auction_start_time || start_price <= end_price {
-            return Err(StdError::generic_err("Invalid auction parameters"));
-        }
-        
-        let time_elapsed = current_time - auction_start_time;
-        let total_duration = auction_end_time - auction_start_time;
-        let price_decrease = start_price.checked_sub(end_price)?;
-        
-        // Linear price decay: currentPrice = startPrice - (priceDecrease * timeElapsed / totalDuration)
-        let price_reduction = price_decrease
-            .checked_mul(Uint128::from(time_elapsed))?
-            .checked_div(Uint128::from(total_duration))?;
-            
-        Ok(start_price.checked_sub(price_reduction)?)
-    }
-    
+    /// Calculate the current price of `order` on its auction curve
+    pub fn get_current_price(order: &Order, current_time: u64) -> StdResult<Uint128> {
+        let points = Self::points(order);
+        Self::auction(order, &points)
+            .price(current_time)
+            .map(Uint128::new)
+            .map_err(auction_error)
+    }
+
     /// Calculate the taking amount based on current auction price
     pub fn calculate_taking_amount(
         making_amount: Uint128,
-        start_price: Uint128,
-        end_price: Uint128,
-        auction_start_time: u64,
-        auction_end_time: u64,
+        order: &Order,
         current_time: u64,
     ) -> StdResult<Uint128> {
-        let current_price = Self::get_current_price(
-            start_price,
-            end_price,
-            auction_start_time,
-            auction_end_time,
-            current_time,
-        )?;
-        
-        // takingAmount = makingAmount * currentPrice / 10^18 (assuming 18 decimal precision)
-        let precision = Uint128::from(10u128.pow(18));
-        Ok(making_amount
-            .checked_mul(current_price)?
-            .checked_div(precision)?)
-    }
-}
\ No newline at end of file
+        // takingAmount = makingAmount * currentPrice / 10^18
+        let points = Self::points(order);
+        Self::auction(order, &points)
+            .taking_amount(making_amount.u128(), current_time)
+            .map(Uint128::new)
+            .map_err(auction_error)
+    }
+
+    fn points(order: &Order) -> Vec<Point> {
+        match &order.auction_details {
+            AuctionDetails::PiecewiseLinear { points, .. } => points
+                .iter()
+                .map(|point| Point { delay: point.delay, rate_bump: point.rate_bump })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn auction<'a>(order: &Order, points: &'a [Point]) -> Auction<'a> {
+        let curve = match order.auction_details {
+            AuctionDetails::Linear => Curve::Linear,
+            AuctionDetails::PiecewiseLinear { initial_rate_bump, .. } => {
+                Curve::PiecewiseLinear { initial_rate_bump, points }
+            }
+            AuctionDetails::Exponential { half_life } => Curve::Exponential { half_life },
+        };
+        Auction {
+            start_price: order.start_price.u128(),
+            end_price: order.end_price.u128(),
+            start_time: order.auction_start_time,
+            end_time: order.auction_end_time,
+            curve,
+        }
+    }
+}
+
+fn auction_error(error: Error) -> StdError {
+    match error {
+        Error::NotStarted => StdError::generic_err("Auction not started"),
+        Error::InvalidDuration | Error::InvalidPrice => invalid_parameters(),
+        Error::Overflow => StdError::generic_err("Auction amount overflow"),
+    }
+}
+
+fn invalid_parameters() -> StdError {
+    StdError::generic_err("Invalid auction parameters")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::Addr;
+    use proptest::prelude::*;
+    use unite_auction::vectors;
+    use unite_order::{AuctionPoint, RATE_BUMP_DENOMINATOR};
+
+    const START: u64 = 1_700_000_000;
+    const DURATION: u64 = 600;
+
+    fn order(start_price: u128, end_price: u128, auction_details: AuctionDetails) -> Order {
+        Order {
+            salt: Uint128::zero(),
+            maker: Addr::unchecked("maker"),
+            receiver: None,
+            maker_asset: "uosmo".to_string(),
+            taker_asset: "uusdc".to_string(),
+            making_amount: Uint128::new(1_000_000),
+            taking_amount: Uint128::new(1_000_000),
+            deadline: START + DURATION,
+            nonce: Uint128::zero(),
+            src_chain_id: 1,
+            dst_chain_id: 2,
+            auction_start_time: START,
+            auction_end_time: START + DURATION,
+            start_price: Uint128::new(start_price),
+            end_price: Uint128::new(end_price),
+            auction_details,
+        }
+    }
+
+    fn price(order: &Order, elapsed: u64) -> u128 {
+        DutchAuction::get_current_price(order, START + elapsed).unwrap().u128()
+    }
+
+    #[test]
+    fn shared_vectors() {
+        for vector in vectors::linear().chain(vectors::curves()) {
+            let auction_details = match vector.curve() {
+                Curve::Linear => AuctionDetails::Linear,
+                Curve::PiecewiseLinear { initial_rate_bump, points } => AuctionDetails::PiecewiseLinear {
+                    initial_rate_bump,
+                    points: points
+                        .iter()
+                        .map(|point| AuctionPoint { delay: point.delay, rate_bump: point.rate_bump })
+                        .collect(),
+                },
+                Curve::Exponential { half_life } => AuctionDetails::Exponential { half_life },
+            };
+            let mut order = order(vector.start_price, vector.end_price, auction_details);
+            order.auction_start_time = vector.start_time;
+            order.auction_end_time = vector.end_time;
+
+            let making_amount = Uint128::new(vector.making_amount);
+            let price = DutchAuction::get_current_price(&order, vector.now);
+            let taking_amount = DutchAuction::calculate_taking_amount(making_amount, &order, vector.now);
+            match vector.expected {
+                Ok((expected_price, expected_taking_amount)) => {
+                    assert_eq!(price, Ok(Uint128::new(expected_price)), "{vector:?}");
+                    assert_eq!(taking_amount, Ok(Uint128::new(expected_taking_amount)), "{vector:?}");
+                }
+                Err(error) => assert_eq!(taking_amount, Err(auction_error(error)), "{vector:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn piecewise_curve_runs_through_points() {
+        let order = order(0, PRICE_PRECISION, AuctionDetails::PiecewiseLinear {
+            initial_rate_bump: 1_000_000,
+            points: vec![
+                AuctionPoint { delay: 100, rate_bump: 500_000 },
+                AuctionPoint { delay: 100, rate_bump: 400_000 },
+            ],
+        });
+        assert_eq!(price(&order, 0), 1_100_000_000_000_000_000);
+        assert_eq!(price(&order, 50), 1_075_000_000_000_000_000);
+        assert_eq!(price(&order, 100), 1_050_000_000_000_000_000);
+        assert_eq!(price(&order, 200), 1_040_000_000_000_000_000);
+        // Last point down to no bump at the end of the auction
+        assert_eq!(price(&order, 400), 1_020_000_000_000_000_000);
+        assert_eq!(price(&order, DURATION), PRICE_PRECISION);
+    }
+
+    #[test]
+    fn exponential_curve_halves_the_gap() {
+        let order = order(2 * PRICE_PRECISION, PRICE_PRECISION, AuctionDetails::Exponential { half_life: 100 });
+        assert_eq!(price(&order, 0), 2 * PRICE_PRECISION);
+        assert_eq!(price(&order, 50), 1_750_000_000_000_000_000);
+        assert_eq!(price(&order, 100), 1_500_000_000_000_000_000);
+        assert_eq!(price(&order, 200), 1_250_000_000_000_000_000);
+        assert_eq!(price(&order, DURATION), PRICE_PRECISION);
+    }
+
+    #[test]
+    fn rejects_invalid_curves() {
+        let increasing = order(0, PRICE_PRECISION, AuctionDetails::PiecewiseLinear {
+            initial_rate_bump: 100,
+            points: vec![AuctionPoint { delay: 10, rate_bump: 200 }],
+        });
+        let too_long = order(0, PRICE_PRECISION, AuctionDetails::PiecewiseLinear {
+            initial_rate_bump: 100,
+            points: vec![AuctionPoint { delay: DURATION as u32 + 1, rate_bump: 0 }],
+        });
+        let no_half_life = order(2, 1, AuctionDetails::Exponential { half_life: 0 });
+        for order in [increasing, too_long, no_half_life] {
+            assert_eq!(
+                DutchAuction::get_current_price(&order, START).unwrap_err(),
+                invalid_parameters()
+            );
+        }
+    }
+
+    fn curve() -> impl Strategy<Value = Order> {
+        let linear = (1u128..u64::MAX as u128, 0u128..u64::MAX as u128)
+            .prop_map(|(gap, end)| order(end + gap, end, AuctionDetails::Linear));
+        let piecewise = (
+            0u128..u64::MAX as u128,
+            0u32..RATE_BUMP_DENOMINATOR,
+            prop::collection::vec((0u32..100, 0u32..RATE_BUMP_DENOMINATOR), 0..5),
+        )
+            .prop_map(|(end, initial_rate_bump, mut points)| {
+                // Bumps sorted high to low under the initial one
+                points.sort_by_key(|&(_, rate_bump)| std::cmp::Reverse(rate_bump));
+                let points = points
+                    .into_iter()
+                    .map(|(delay, rate_bump)| AuctionPoint { delay, rate_bump: rate_bump.min(initial_rate_bump) })
+                    .collect();
+                order(0, end, AuctionDetails::PiecewiseLinear { initial_rate_bump, points })
+            });
+        let exponential = (0u128..u64::MAX as u128, 0u128..u64::MAX as u128, 1u64..1_000)
+            .prop_map(|(gap, end, half_life)| order(end + gap, end, AuctionDetails::Exponential { half_life }));
+        prop_oneof![linear, piecewise, exponential]
+    }
+
+    /// Highest price the curve may quote
+    fn ceiling(order: &Order) -> u128 {
+        match &order.auction_details {
+            AuctionDetails::PiecewiseLinear { initial_rate_bump, .. } => {
+                order.end_price.u128() * u128::from(RATE_BUMP_DENOMINATOR + initial_rate_bump)
+                    / u128::from(RATE_BUMP_DENOMINATOR)
+            }
+            _ => order.start_price.u128(),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prices_never_increase(order in curve(), a in 0..DURATION + 100, b in 0..DURATION + 100) {
+            let (earlier, later) = (a.min(b), a.max(b));
+            prop_assert!(price(&order, earlier) >= price(&order, later));
+        }
+
+        #[test]
+        fn prices_stay_within_bounds(order in curve(), elapsed in 0..DURATION + 100) {
+            let price = price(&order, elapsed);
+            prop_assert!(price >= order.end_price.u128());
+            prop_assert!(price <= ceiling(&order));
+            if elapsed >= DURATION {
+                prop_assert_eq!(price, order.end_price.u128());
+            }
+        }
+    }
+}
@@ -18,8 +18,44 @@ pub enum ContractError {
     #[error("Invalid source amount")]
     InvalidSrcAmount {},
 
-    #[error("Order completed")]
-    OrderCompleted {},
+    #[error("Invalid signature")]
+    InvalidSignature {},
+
+    #[error("Order expired")]
+    OrderExpired {},
+
+    #[error("Auction not started")]
+    AuctionNotStarted {},
+
+    #[error("Immutables order hash does not match the order")]
+    OrderHashMismatch {},
+
+    #[error("Immutables maker does not match the order")]
+    MakerMismatch {},
+
+    #[error("Immutables token does not match the order's asset")]
+    AssetMismatch {},
+
+    #[error("CW20 destination tokens are not supported")]
+    Cw20NotSupported {},
+
+    #[error("Immutables amount does not match the order")]
+    AmountMismatch {},
+
+    #[error("Destination escrow would be cancellable after the source escrow")]
+    InvalidSrcCancellationTimestamp {},
+
+    #[error("Decimals of {asset} on chain {chain_id} are not registered")]
+    UnknownTokenDecimals { chain_id: u64, asset: String },
+
+    #[error("Unsupported token decimals: {decimals}")]
+    InvalidDecimals { decimals: u8 },
+
+    #[error("Escrow address not found in factory reply")]
+    EscrowAddressNotFound {},
+
+    #[error("Filled amount not found in order protocol reply")]
+    FilledAmountNotFound {},
 
     #[error("Unknown reply id: {id}")]
     UnknownReplyId { id: u64 },
@@ -4,5 +4,9 @@ pub mod msg;
 pub mod state;
 pub mod types;
 pub mod dutch_auction;
+pub mod decimals;
 
-pub use crate::error::ContractError;
\ No newline at end of file
+#[cfg(test)]
+mod tests;
+
+pub use crate::error::ContractError;
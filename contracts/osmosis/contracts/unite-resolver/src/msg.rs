@@ -1,13 +1,20 @@
 use cosmwasm_std::{Addr, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use crate::types::{Order, Immutables};
+use unite_order::OrderSignature;
+
+use crate::types::{Order, Immutables, TokenDecimals};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
     pub factory: Addr,
     pub order_protocol: Addr,
+    /// Bank denom in which resolvers post escrow safety deposits
+    pub safety_deposit_denom: String,
+    /// Initial token registry, extended with `SetTokenDecimals`
+    #[serde(default)]
+    pub token_decimals: Vec<TokenDecimals>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -16,13 +23,13 @@ pub enum ExecuteMsg {
     DeploySrc {
         immutables: Immutables,
         order: Order,
-        signature: String,
+        signature: OrderSignature,
         amount: Uint128,
     },
     DeploySrcPartial {
         immutables: Immutables,
         order: Order,
-        signature: String,
+        signature: OrderSignature,
         amount: Uint128,
         partial_amount: Uint128,
     },
@@ -41,6 +48,12 @@ pub enum ExecuteMsg {
         src_cancellation_timestamp: u64,
         src_amount: Uint128,
     },
+    /// Registers or updates the decimals of an asset, owner only
+    SetTokenDecimals {
+        chain_id: u64,
+        asset: String,
+        decimals: u8,
+    },
     ApproveToken {
         token: String,
         amount: Uint128,
@@ -49,6 +62,9 @@ pub enum ExecuteMsg {
         escrow: Addr,
         secret: String,
         immutables: Immutables,
+        /// Secret index and merkle proof for multi-fill escrows
+        index: Option<u16>,
+        proof: Option<Vec<String>>,
     },
     Cancel {
         escrow: Addr,
@@ -60,7 +76,9 @@ pub enum ExecuteMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetConfig {},
-    GetOrderHash { order: Order },
+    GetOrderHash { order: Box<Order> },
+    GetEscrowAddress { order_hash: String, is_source: bool },
+    GetTokenDecimals { chain_id: u64, asset: String },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -68,4 +86,14 @@ pub struct ConfigResponse {
     pub owner: Addr,
     pub factory: Addr,
     pub order_protocol: Addr,
-}
\ No newline at end of file
+    pub safety_deposit_denom: String,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EscrowAddressResponse {
+    pub escrow_address: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenDecimalsResponse {
+    pub decimals: Option<u8>,
+}
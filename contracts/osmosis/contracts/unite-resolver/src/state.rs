@@ -1,15 +1,48 @@
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Uint128};
 use cw_storage_plus::{Item, Map};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::types::{Immutables, Order};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub owner: Addr,
     pub factory: Addr,
     pub order_protocol: Addr,
+    pub safety_deposit_denom: String,
+}
+
+/// Fill waiting on the order protocol's reply to deploy its destination escrow
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingFill {
+    pub immutables: Immutables,
+    pub order: Order,
+    pub src_cancellation_timestamp: u64,
+    pub resolver: Addr,
+    pub safety_deposit: Uint128,
+}
+
+/// Source fill waiting on the order protocol's reply to deploy or join its source escrow
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingSrcFill {
+    pub immutables: Immutables,
+    pub resolver: Addr,
+    pub safety_deposit: Uint128,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
-pub const ORDER_FILLS: Map<String, u128> = Map::new("order_fills");
-pub const ESCROW_ADDRESSES: Map<String, Addr> = Map::new("escrow_addresses");
\ No newline at end of file
+pub const PENDING_FILL: Item<PendingFill> = Item::new("pending_fill");
+pub const PENDING_SRC_FILL: Item<PendingSrcFill> = Item::new("pending_src_fill");
+// (chain_id, asset) => token decimals, to convert amounts between chains
+pub const TOKEN_DECIMALS: Map<(u64, &str), u8> = Map::new("token_decimals");
+// (order_hash, escrow type key) => escrow address, filled from factory replies
+pub const ESCROW_ADDRESSES: Map<(&str, &str), Addr> = Map::new("escrow_addresses");
+
+pub fn escrow_type_key(is_source: bool) -> &'static str {
+    if is_source {
+        "src"
+    } else {
+        "dst"
+    }
+}
\ No newline at end of file
@@ -0,0 +1,601 @@
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{
+    coins, from_json, to_json_binary, Addr, Binary, CosmosMsg, Event, OwnedDeps, Reply, ReplyOn,
+    Response, SubMsgResponse, SubMsgResult, Timestamp, Uint128, Uint256, WasmMsg,
+};
+use serde::Serialize;
+use unite_order::{order_hash_hex, AuctionDetails, Eip712Domain, OrderSignature};
+use unite_timelocks::Timelocks as PackedTimelocks;
+
+use crate::contract::{execute, instantiate, query, reply};
+use crate::msg::{EscrowAddressResponse, ExecuteMsg, InstantiateMsg, QueryMsg, TokenDecimalsResponse};
+use crate::types::{AssetInfo, Immutables, Order, Timelocks, TokenDecimals};
+use crate::ContractError;
+
+const OWNER: &str = "owner";
+const FACTORY: &str = "factory";
+const ESCROW: &str = "escrow";
+const ORDER_PROTOCOL: &str = "order_protocol";
+const DENOM: &str = "uosmo";
+const ORDER_HASH: &str = "order";
+const DAI: &str = "0x6B175474E89094C44Da98b954EedeAC495271d0F";
+const OSMOSIS_CHAIN_ID: u64 = 5555;
+const BASE_CHAIN_ID: u64 = 84532;
+// Order below signed by the key 0x1111..11 with `eth_signTypedData_v4`
+const MAKER: &str = "0x19E7E376E7C213B7E7e7e46cc70A5dD086DAff2A";
+const EIP712_SIGNATURE: &str = "d636f07c02aaa9fd6bfb75c396f018e96f7534a4da2829f51bac3d7f3c78c33a075f9fe0f0b6b96f70434ec57d2ebdc1f88a05d017817bedaa1890bf21e8ab081c";
+
+#[derive(Serialize)]
+struct FillOrderResponse {
+    order_hash: String,
+    making_amount: Uint128,
+}
+
+#[derive(Serialize)]
+struct CreateEscrowResponse {
+    escrow_address: Addr,
+    order_hash: String,
+    is_source: bool,
+}
+
+fn setup() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        InstantiateMsg {
+            factory: Addr::unchecked(FACTORY),
+            order_protocol: Addr::unchecked(ORDER_PROTOCOL),
+            safety_deposit_denom: DENOM.to_string(),
+            token_decimals: vec![
+                TokenDecimals { chain_id: OSMOSIS_CHAIN_ID, asset: DENOM.to_string(), decimals: 6 },
+                TokenDecimals { chain_id: BASE_CHAIN_ID, asset: DAI.to_string(), decimals: 18 },
+            ],
+        },
+    )
+    .unwrap();
+    deps
+}
+
+fn immutables() -> Immutables {
+    Immutables {
+        order_hash: ORDER_HASH.to_string(),
+        hashlock: "hashlock".to_string(),
+        maker: Addr::unchecked("maker"),
+        taker: Addr::unchecked("taker"),
+        token: AssetInfo::Native(DENOM.to_string()),
+        amount: Uint128::new(1000),
+        safety_deposit: Uint128::new(100),
+        timelocks: Timelocks(Uint256::zero()),
+        parts_amount: None,
+    }
+}
+
+fn order() -> Order {
+    Order {
+        salt: Uint128::new(42),
+        maker: Addr::unchecked(MAKER),
+        receiver: None,
+        maker_asset: DENOM.to_string(),
+        taker_asset: DAI.to_string(),
+        making_amount: Uint128::new(1_000_000),
+        taking_amount: Uint128::new(1_000_000),
+        deadline: 1_700_003_600,
+        nonce: Uint128::zero(),
+        src_chain_id: OSMOSIS_CHAIN_ID,
+        dst_chain_id: BASE_CHAIN_ID,
+        auction_start_time: 1_700_000_000,
+        auction_end_time: 1_700_000_300,
+        start_price: Uint128::new(1_000_000_000_000_000_000),
+        end_price: Uint128::new(1_000_000_000_000_000_000),
+        auction_details: AuctionDetails::Linear,
+    }
+}
+
+fn signature() -> OrderSignature {
+    OrderSignature::Eip712 {
+        domain: Eip712Domain::unite(84532, "0x734F3DDcE982B1b966B4234C53a1365D44980692"),
+        signature: Binary::from(hex::decode(EIP712_SIGNATURE).unwrap()),
+    }
+}
+
+/// Wraps contract data the way wasmd returns it for a `MsgExecuteContract` submessage
+fn execute_response_data(data: &Binary) -> Binary {
+    let mut encoded = vec![0x0a];
+    let mut len = data.len();
+    while len >= 0x80 {
+        encoded.push((len as u8) | 0x80);
+        len >>= 7;
+    }
+    encoded.push(len as u8);
+    encoded.extend_from_slice(data);
+    Binary::from(encoded)
+}
+
+fn escrow_address(
+    deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    is_source: bool,
+) -> Option<Addr> {
+    let res: EscrowAddressResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetEscrowAddress {
+                order_hash: ORDER_HASH.to_string(),
+                is_source,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    res.escrow_address
+}
+
+#[test]
+fn source_fills_go_through_order_protocol_and_factory() {
+    let mut deps = setup();
+    let order = order();
+    let deploy_src = ExecuteMsg::DeploySrcPartial {
+        immutables: src_immutables(&order),
+        order: order.clone(),
+        signature: signature(),
+        amount: Uint128::new(1000),
+        partial_amount: Uint128::new(600_000),
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &coins(100, DENOM)),
+        deploy_src,
+    )
+    .unwrap();
+    assert_eq!(res.messages[0].reply_on, ReplyOn::Success);
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, msg, funds }) => {
+            assert_eq!(contract_addr, ORDER_PROTOCOL);
+            assert!(funds.is_empty());
+            let msg: serde_json::Value = from_json(msg).unwrap();
+            assert_eq!(msg["fill_order"]["making_amount"], "600000");
+            assert_eq!(msg["fill_order"]["taking_amount"], "600000");
+        }
+        msg => panic!("unexpected message: {msg:?}"),
+    }
+
+    // Only 400_000 was left on the order, so the escrow is joined for that much
+    let data = to_json_binary(&FillOrderResponse {
+        order_hash: order_hash_hex(&order),
+        making_amount: Uint128::new(400_000),
+    })
+    .unwrap();
+    let res = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: res.messages[0].id,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(execute_response_data(&data)),
+            }),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages[0].reply_on, ReplyOn::Success);
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, msg, funds }) => {
+            assert_eq!(contract_addr, FACTORY);
+            assert_eq!(funds, &coins(100, DENOM));
+            let msg: serde_json::Value = from_json(msg).unwrap();
+            assert_eq!(msg["create_src_escrow_partial_for"]["partial_amount"], "400000");
+            assert_eq!(msg["create_src_escrow_partial_for"]["resolver"], OWNER);
+        }
+        msg => panic!("unexpected message: {msg:?}"),
+    }
+    assert!(escrow_address(&deps, true).is_none());
+
+    let data = to_json_binary(&CreateEscrowResponse {
+        escrow_address: Addr::unchecked(ESCROW),
+        order_hash: ORDER_HASH.to_string(),
+        is_source: true,
+    })
+    .unwrap();
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: res.messages[0].id,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(execute_response_data(&data)),
+            }),
+        },
+    )
+    .unwrap();
+    assert_eq!(escrow_address(&deps, true), Some(Addr::unchecked(ESCROW)));
+    assert!(escrow_address(&deps, false).is_none());
+}
+
+#[test]
+fn reply_falls_back_to_factory_event() {
+    let mut deps = setup();
+
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: 1,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![Event::new("wasm")
+                    .add_attribute("_contract_address", FACTORY)
+                    .add_attribute("method", "create_dst_escrow")
+                    .add_attribute("escrow_address", ESCROW)
+                    .add_attribute("order_hash", ORDER_HASH)],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(escrow_address(&deps, false), Some(Addr::unchecked(ESCROW)));
+    assert!(escrow_address(&deps, true).is_none());
+}
+
+#[test]
+fn deploy_dst_rejects_cw20_tokens() {
+    let mut deps = setup();
+    let immutables = Immutables { token: AssetInfo::Cw20(Addr::unchecked("usdc")), ..immutables() };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &coins(100, DENOM)),
+        ExecuteMsg::DeployDst { immutables, src_cancellation_timestamp: 0 },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Cw20NotSupported {}));
+}
+
+#[test]
+fn deploy_src_rejects_invalid_signature() {
+    let mut deps = setup();
+    let mut tampered = order();
+    tampered.taking_amount = Uint128::new(1);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &coins(100, DENOM)),
+        ExecuteMsg::DeploySrc {
+            immutables: src_immutables(&tampered),
+            order: tampered,
+            signature: signature(),
+            amount: Uint128::new(1000),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidSignature {}));
+}
+
+#[test]
+fn deploy_src_checks_immutables_against_order() {
+    let mut deps = setup();
+    let deploy = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, immutables: Immutables| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &coins(100, DENOM)),
+            ExecuteMsg::DeploySrc {
+                immutables,
+                order: order(),
+                signature: signature(),
+                amount: Uint128::new(1000),
+            },
+        )
+    };
+
+    let cases = [
+        (Immutables { order_hash: ORDER_HASH.to_string(), ..src_immutables(&order()) }, ContractError::OrderHashMismatch {}),
+        (Immutables { maker: Addr::unchecked("maker"), ..src_immutables(&order()) }, ContractError::MakerMismatch {}),
+        (Immutables { token: AssetInfo::Native("uion".to_string()), ..src_immutables(&order()) }, ContractError::AssetMismatch {}),
+        (Immutables { amount: Uint128::new(1), ..src_immutables(&order()) }, ContractError::AmountMismatch {}),
+    ];
+    for (immutables, expected) in cases {
+        let err = deploy(&mut deps, immutables).unwrap_err();
+        assert_eq!(err.to_string(), expected.to_string());
+    }
+    deploy(&mut deps, src_immutables(&order())).unwrap();
+}
+
+/// Source immutables locking the whole of `order`
+fn src_immutables(order: &Order) -> Immutables {
+    Immutables {
+        order_hash: order_hash_hex(order),
+        maker: order.maker.clone(),
+        token: AssetInfo::Native(order.maker_asset.clone()),
+        amount: order.making_amount,
+        ..immutables()
+    }
+}
+
+/// Destination immutables for `order`, cancellable 1000s after deployment
+fn fill_immutables(order: &Order) -> Immutables {
+    let timelocks = PackedTimelocks::encode([0, 0, 0, 0, 0, 500, 1000], 0);
+    Immutables {
+        order_hash: order_hash_hex(order),
+        maker: order.maker.clone(),
+        token: AssetInfo::Native(order.taker_asset.clone()),
+        amount: whole_order_taking_amount(order),
+        timelocks: Timelocks(Uint256::from_be_bytes(timelocks.to_be_bytes())),
+        ..immutables()
+    }
+}
+
+/// Taking amount for all of `order` at its start price, in the taker asset's decimals
+fn whole_order_taking_amount(order: &Order) -> Uint128 {
+    let decimals = |asset: &str| match asset {
+        DAI => 18,
+        DENOM => 6,
+        _ => 8,
+    };
+    crate::decimals::taking_amount(
+        order.making_amount,
+        decimals(&order.maker_asset),
+        order.start_price,
+        decimals(&order.taker_asset),
+    )
+    .unwrap()
+    .amount
+}
+
+/// Fills at the end of the auction, the order protocol filling `filled` of `src_amount`.
+/// Returns the response to the order protocol's reply.
+fn fill_partially(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    immutables: Immutables,
+    order: Order,
+    src_amount: u128,
+    filled: u128,
+    src_cancellation_timestamp: u64,
+) -> Result<Response, ContractError> {
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(order.auction_end_time);
+    let order_hash = order_hash_hex(&order);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(OWNER, &coins(100, DENOM)),
+        ExecuteMsg::FillOrder {
+            immutables,
+            order,
+            src_cancellation_timestamp,
+            src_amount: Uint128::new(src_amount),
+        },
+    )?;
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => {
+            assert_eq!(contract_addr, ORDER_PROTOCOL)
+        }
+        msg => panic!("unexpected message: {msg:?}"),
+    }
+
+    let data = to_json_binary(&FillOrderResponse { order_hash, making_amount: Uint128::new(filled) }).unwrap();
+    reply(
+        deps.as_mut(),
+        env,
+        Reply {
+            id: res.messages[0].id,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(execute_response_data(&data)),
+            }),
+        },
+    )
+}
+
+fn fill(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    immutables: Immutables,
+    order: Order,
+    src_amount: u128,
+    src_cancellation_timestamp: u64,
+) -> Result<Response, ContractError> {
+    fill_partially(deps, immutables, order, src_amount, src_amount, src_cancellation_timestamp)
+}
+
+fn fill_order(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    order: Order,
+    src_amount: u128,
+) -> Result<Response, ContractError> {
+    let src_cancellation_timestamp = order.auction_end_time + 1000;
+    fill(deps, fill_immutables(&order), order, src_amount, src_cancellation_timestamp)
+}
+
+fn attribute<'a>(res: &'a Response, key: &str) -> &'a str {
+    &res.attributes.iter().find(|attr| attr.key == key).unwrap().value
+}
+
+#[test]
+fn fill_order_converts_between_token_decimals() {
+    let mut deps = setup();
+
+    // 0.123456 OSMO at 6 decimals for DAI at 18, one for one
+    let res = fill_order(&mut deps, order(), 123_456).unwrap();
+    assert_eq!(attribute(&res, "dest_amount"), "123456000000000000");
+    assert_eq!(attribute(&res, "rounding_dust"), "0");
+
+    // The other way round, DAI's last decimals round up to the next uosmo for the maker
+    let mut order = order();
+    order.src_chain_id = BASE_CHAIN_ID;
+    order.maker_asset = DAI.to_string();
+    order.dst_chain_id = OSMOSIS_CHAIN_ID;
+    order.taker_asset = DENOM.to_string();
+    order.making_amount = Uint128::new(10_000_000_000_000_000_000);
+    let res = fill_order(&mut deps, order, 1_234_567_800_000_000_000).unwrap();
+    assert_eq!(attribute(&res, "dest_amount"), "1234568");
+    assert_eq!(attribute(&res, "rounding_dust"), "200000000000");
+    assert_eq!(attribute(&res, "src_decimals"), "18");
+    assert_eq!(attribute(&res, "dst_decimals"), "6");
+}
+
+#[test]
+fn fill_order_requires_registered_decimals() {
+    let mut deps = setup();
+    let mut order = order();
+    order.taker_asset = "0xunknown".to_string();
+
+    let err = fill_order(&mut deps, order.clone(), 1_000).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::UnknownTokenDecimals { chain_id: BASE_CHAIN_ID, ref asset } if asset == "0xunknown"
+    ));
+
+    let register = ExecuteMsg::SetTokenDecimals {
+        chain_id: BASE_CHAIN_ID,
+        asset: "0xunknown".to_string(),
+        decimals: 8,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), register.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), register).unwrap();
+
+    let res: TokenDecimalsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetTokenDecimals { chain_id: BASE_CHAIN_ID, asset: "0xunknown".to_string() },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.decimals, Some(8));
+    // 1000 uosmo is 0.001 of a token, 100000 units at 8 decimals
+    let res = fill_order(&mut deps, order, 1_000).unwrap();
+    assert_eq!(attribute(&res, "dest_amount"), "100000");
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::SetTokenDecimals { chain_id: BASE_CHAIN_ID, asset: DAI.to_string(), decimals: 19 },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidDecimals { decimals: 19 }));
+}
+
+#[test]
+fn fill_order_validates_against_the_order() {
+    let mut deps = setup();
+    let order = order();
+    let src_cancellation_timestamp = order.auction_end_time + 1000;
+    let mut check = |immutables: Immutables, order: Order, src_cancellation_timestamp: u64| {
+        fill(&mut deps, immutables, order, 1_000, src_cancellation_timestamp).unwrap_err()
+    };
+
+    let mut other = order.clone();
+    other.salt = Uint128::new(43);
+    let err = check(fill_immutables(&other), order.clone(), src_cancellation_timestamp);
+    assert!(matches!(err, ContractError::OrderHashMismatch {}));
+
+    let immutables = Immutables { maker: Addr::unchecked("resolver"), ..fill_immutables(&order) };
+    let err = check(immutables, order.clone(), src_cancellation_timestamp);
+    assert!(matches!(err, ContractError::MakerMismatch {}));
+
+    // With a receiver the escrow pays the receiver instead
+    let mut with_receiver = order.clone();
+    with_receiver.receiver = Some(Addr::unchecked("receiver"));
+    let err = check(fill_immutables(&with_receiver), with_receiver, src_cancellation_timestamp);
+    assert!(matches!(err, ContractError::MakerMismatch {}));
+
+    let immutables = Immutables { token: AssetInfo::Native(DENOM.to_string()), ..fill_immutables(&order) };
+    let err = check(immutables, order.clone(), src_cancellation_timestamp);
+    assert!(matches!(err, ContractError::AssetMismatch {}));
+
+    let mut expired = order.clone();
+    expired.deadline = expired.auction_end_time;
+    let err = check(fill_immutables(&expired), expired, src_cancellation_timestamp);
+    assert!(matches!(err, ContractError::OrderExpired {}));
+
+    let mut not_started = order.clone();
+    not_started.auction_start_time = not_started.auction_end_time + 1;
+    not_started.deadline = not_started.auction_end_time + 3600;
+    let err = check(fill_immutables(&not_started), not_started, src_cancellation_timestamp);
+    assert!(matches!(err, ContractError::AuctionNotStarted {}));
+
+    // An escrow too small for the 0.001 DAI the fill owes the maker
+    let immutables = Immutables { amount: Uint128::new(999_999_999_999_999), ..fill_immutables(&order) };
+    let err = check(immutables, order.clone(), src_cancellation_timestamp);
+    assert!(matches!(err, ContractError::AmountMismatch {}));
+    
+    // The destination escrow would become cancellable a second after the source one
+    let err = check(fill_immutables(&order), order.clone(), src_cancellation_timestamp - 1);
+    assert!(matches!(err, ContractError::InvalidSrcCancellationTimestamp {}));
+
+    let mut with_receiver = order.clone();
+    with_receiver.receiver = Some(Addr::unchecked("receiver"));
+    let immutables = Immutables { maker: Addr::unchecked("receiver"), ..fill_immutables(&with_receiver) };
+    fill(&mut deps, immutables, with_receiver, 1_000, src_cancellation_timestamp).unwrap();
+}
+
+#[test]
+fn fill_order_deploys_for_the_amount_the_protocol_filled() {
+    let mut deps = setup();
+    let order = order();
+    let src_cancellation_timestamp = order.auction_end_time + 1000;
+
+    // Only 400_000 of the 600_000 asked for was left on the order
+    let res = fill_partially(&mut deps, fill_immutables(&order), order, 600_000, 400_000, src_cancellation_timestamp)
+        .unwrap();
+    assert_eq!(attribute(&res, "making_amount"), "400000");
+    assert_eq!(attribute(&res, "dest_amount"), "400000000000000000");
+    assert_eq!(res.messages[0].reply_on, ReplyOn::Success);
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, msg, funds }) => {
+            assert_eq!(contract_addr, FACTORY);
+            assert_eq!(funds, &coins(100, DENOM));
+            let msg: serde_json::Value = from_json(msg).unwrap();
+            assert_eq!(msg["create_dst_escrow_partial_for"]["partial_amount"], "400000000000000000");
+        }
+        msg => panic!("unexpected message: {msg:?}"),
+    }
+}
+
+#[test]
+fn fill_reply_falls_back_to_protocol_event() {
+    let mut deps = setup();
+    let order = order();
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(order.auction_end_time);
+    let order_hash = order_hash_hex(&order);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(OWNER, &coins(100, DENOM)),
+        ExecuteMsg::FillOrder {
+            immutables: fill_immutables(&order),
+            src_cancellation_timestamp: order.auction_end_time + 1000,
+            order,
+            src_amount: Uint128::new(1_000),
+        },
+    )
+    .unwrap();
+
+    let res = reply(
+        deps.as_mut(),
+        env,
+        Reply {
+            id: res.messages[0].id,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![Event::new("wasm")
+                    .add_attribute("_contract_address", ORDER_PROTOCOL)
+                    .add_attribute("method", "fill_order")
+                    .add_attribute("order_hash", order_hash)
+                    .add_attribute("making_amount", "1000")],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+    assert_eq!(attribute(&res, "dest_amount"), "1000000000000000");
+}
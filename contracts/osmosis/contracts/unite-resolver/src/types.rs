@@ -1,46 +1,60 @@
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Uint128, Uint256};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use unite_timelocks::{Stage, Timelocks as PackedTimelocks};
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct Order {
-    pub salt: u64,
-    pub maker: Addr,
-    pub receiver: Addr,
-    pub maker_asset: String,  // Token denom on source chain
-    pub taker_asset: String,  // Token denom on destination chain  
-    pub making_amount: Uint128,
-    pub taking_amount: Uint128,
-    pub deadline: u64,
-    pub nonce: u64,
-    pub src_chain_id: String,
-    pub dst_chain_id: String,
-    pub auction_start_time: u64,
-    pub auction_end_time: u64,
-    pub start_price: Uint128,  // Price per unit with 18 decimals precision
-    pub end_price: Uint128,    // Price per unit with 18 decimals precision
-}
+pub use unite_order::Order;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Immutables {
     pub order_hash: String,
+    /// `sha256(secret)`, or the merkle root of the secrets when `parts_amount` is set
     pub hashlock: String,
     pub maker: Addr,
     pub taker: Addr,
-    pub token: String,
+    pub token: AssetInfo,
     pub amount: Uint128,
     pub safety_deposit: Uint128,
     pub timelocks: Timelocks,
+    /// Number of parts a multi-fill order is split into, `None` for a single secret
+    pub parts_amount: Option<u16>,
 }
 
+/// Escrowed asset: a bank denom (`uosmo`, `ibc/...`, `factory/...`) or a CW20 contract
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct Timelocks {
-    pub src_withdrawal: u64,
-    pub src_public_withdrawal: u64,
-    pub src_cancellation: u64,
-    pub src_public_cancellation: u64,
-    pub dst_withdrawal: u64,
-    pub dst_public_withdrawal: u64,
-    pub dst_cancellation: u64,
-    pub deployed_at: Option<u64>,
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfo {
+    Native(String),
+    Cw20(Addr),
+}
+
+impl AssetInfo {
+    /// Whether this is the asset an order names as `asset`
+    pub fn is(&self, asset: &str) -> bool {
+        match self {
+            AssetInfo::Native(denom) => denom == asset,
+            AssetInfo::Cw20(contract_addr) => contract_addr.as_str() == asset,
+        }
+    }
+}
+
+/// Decimals of `asset` on the chain with `chain_id`, as named in orders
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenDecimals {
+    pub chain_id: u64,
+    pub asset: String,
+    pub decimals: u8,
+}
+
+/// Packed EVM `Timelocks` word, passed through to the escrow as is
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct Timelocks(pub Uint256);
+
+impl Timelocks {
+    /// When a destination escrow deployed at `deployed_at` becomes cancellable
+    pub fn dst_cancellation(&self, deployed_at: u64) -> u64 {
+        PackedTimelocks::from_be_bytes(self.0.to_be_bytes())
+            .with_deployed_at(deployed_at as u32)
+            .get(Stage::DstCancellation)
+    }
 }
\ No newline at end of file
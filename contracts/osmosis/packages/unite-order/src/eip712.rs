@@ -0,0 +1,148 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::hash::{address_word, hash_order, keccak256, uint_word, ORDER_TYPE};
+use crate::order::{AuctionDetails, Order};
+
+pub const DOMAIN_TYPE: &str =
+    "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+/// `SIGNING_DOMAIN` / `SIGNATURE_VERSION` of the EVM `UniteLimitOrderProtocol`
+pub const DOMAIN_NAME: &str = "UniteLimitOrderProtocol";
+pub const DOMAIN_VERSION: &str = "1";
+
+/// Curve of a non-linear auction. `kind` is 1 for piecewise linear and 2 for exponential,
+/// the fields the curve does not use are zero or empty. The exponential curve halves the gap
+/// to `endPrice` every `halfLife` seconds but falls linearly in between and reaches `endPrice`
+/// at `auctionEndTime`, so signers should not expect a true exponential price.
+pub const AUCTION_DETAILS_TYPE: &str = "AuctionDetails(uint8 kind,uint32 initialRateBump,AuctionPoint[] points,uint64 halfLife)";
+pub const AUCTION_POINT_TYPE: &str = "AuctionPoint(uint32 delay,uint32 rateBump)";
+
+/// `ORDER_TYPE` extended with a trailing `AuctionDetails auctionDetails` member, followed by
+/// the referenced types in EIP-712 order. Makers of non-linear orders sign this type.
+pub fn order_with_auction_type() -> String {
+    format!(
+        "{},AuctionDetails auctionDetails){AUCTION_DETAILS_TYPE}{AUCTION_POINT_TYPE}",
+        ORDER_TYPE.strip_suffix(')').unwrap_or(ORDER_TYPE)
+    )
+}
+
+pub fn order_with_auction_typehash() -> [u8; 32] {
+    keccak256(order_with_auction_type().as_bytes())
+}
+
+/// EIP-712 `hashStruct` of the `AuctionDetails` member, `None` for linear orders which
+/// keep the plain EVM `Order` type
+pub fn hash_auction_details(details: &AuctionDetails) -> Option<[u8; 32]> {
+    let (kind, initial_rate_bump, points, half_life) = match details {
+        AuctionDetails::Linear => return None,
+        AuctionDetails::PiecewiseLinear { initial_rate_bump, points } => {
+            (1, *initial_rate_bump, points.as_slice(), 0)
+        }
+        AuctionDetails::Exponential { half_life } => (2, 0, [].as_slice(), *half_life),
+    };
+    let point_typehash = keccak256(AUCTION_POINT_TYPE.as_bytes());
+    let point_hashes: Vec<u8> = points
+        .iter()
+        .flat_map(|point| {
+            let words = [point_typehash, uint_word(point.delay.into()), uint_word(point.rate_bump.into())];
+            keccak256(&words.concat())
+        })
+        .collect();
+    let words = [
+        keccak256(format!("{AUCTION_DETAILS_TYPE}{AUCTION_POINT_TYPE}").as_bytes()),
+        uint_word(kind),
+        uint_word(initial_rate_bump.into()),
+        keccak256(&point_hashes),
+        uint_word(half_life.into()),
+    ];
+    Some(keccak256(&words.concat()))
+}
+
+/// EIP-712 domain of the EVM limit order protocol deployment the maker signed for
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Eip712Domain {
+    pub name: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub verifying_contract: String,
+}
+
+impl Eip712Domain {
+    /// Domain of the `UniteLimitOrderProtocol` deployed at `verifying_contract` on `chain_id`
+    pub fn unite(chain_id: u64, verifying_contract: impl Into<String>) -> Self {
+        Eip712Domain {
+            name: DOMAIN_NAME.to_string(),
+            version: DOMAIN_VERSION.to_string(),
+            chain_id,
+            verifying_contract: verifying_contract.into(),
+        }
+    }
+
+    pub fn separator(&self) -> [u8; 32] {
+        let words = [
+            keccak256(DOMAIN_TYPE.as_bytes()),
+            keccak256(self.name.as_bytes()),
+            keccak256(self.version.as_bytes()),
+            uint_word(self.chain_id.into()),
+            address_word(&self.verifying_contract),
+        ];
+        keccak256(&words.concat())
+    }
+}
+
+/// `keccak256("\x19\x01" || domainSeparator || structHash)`, as OpenZeppelin `_hashTypedDataV4`
+pub fn typed_data_hash(domain_separator: &[u8; 32], struct_hash: &[u8; 32]) -> [u8; 32] {
+    let mut data = [0u8; 66];
+    data[..2].copy_from_slice(b"\x19\x01");
+    data[2..34].copy_from_slice(domain_separator);
+    data[34..].copy_from_slice(struct_hash);
+    keccak256(&data)
+}
+
+/// Digest a maker signs with `eth_signTypedData_v4` for `order`
+pub fn order_digest(domain: &Eip712Domain, order: &Order) -> [u8; 32] {
+    typed_data_hash(&domain.separator(), &hash_order(order))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::tests::{cosmos_order, evm_order, piecewise_order};
+
+    // UniteLimitOrderProtocol on Base Sepolia
+    fn domain() -> Eip712Domain {
+        Eip712Domain::unite(84532, "0x734F3DDcE982B1b966B4234C53a1365D44980692")
+    }
+
+    #[test]
+    fn domain_separator_matches_evm() {
+        assert_eq!(
+            hex::encode(domain().separator()),
+            "394a9a236e2b3ad92a890dca07941933d9e30e2c1f810e8fb9fd412cf22d1a2b"
+        );
+    }
+
+    #[test]
+    fn order_digest_matches_evm_vectors() {
+        assert_eq!(
+            hex::encode(order_digest(&domain(), &evm_order())),
+            "0aa0f85746539df095afd7e5dd3f681c333964aeb18244178f8c53d12d4fd029"
+        );
+        assert_eq!(
+            hex::encode(order_digest(&domain(), &cosmos_order())),
+            "a199348e2c9102da71131215b07c8f62ac37560a8016a5571c50480062bb1c79"
+        );
+    }
+
+    #[test]
+    fn non_linear_orders_sign_the_extended_order_type() {
+        assert_eq!(
+            order_with_auction_type(),
+            "Order(uint256 salt,address maker,address receiver,address makerAsset,address takerAsset,uint256 makingAmount,uint256 takingAmount,uint256 deadline,uint256 nonce,uint256 srcChainId,uint256 dstChainId,uint256 auctionStartTime,uint256 auctionEndTime,uint256 startPrice,uint256 endPrice,AuctionDetails auctionDetails)AuctionDetails(uint8 kind,uint32 initialRateBump,AuctionPoint[] points,uint64 halfLife)AuctionPoint(uint32 delay,uint32 rateBump)"
+        );
+        assert_eq!(
+            hex::encode(order_digest(&domain(), &piecewise_order())),
+            "c694199ba87cf133c13700568ec099ad9ad6c8a2a0359840eb32d2bd5b214678"
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use sha3::{Digest, Keccak256};
+
+use crate::eip712::{hash_auction_details, order_with_auction_typehash};
+use crate::order::Order;
+
+/// EIP-712 type string of the EVM `UniteLimitOrderProtocol.hashOrder`
+pub const ORDER_TYPE: &str = "Order(uint256 salt,address maker,address receiver,address makerAsset,address takerAsset,uint256 makingAmount,uint256 takingAmount,uint256 deadline,uint256 nonce,uint256 srcChainId,uint256 dstChainId,uint256 auctionStartTime,uint256 auctionEndTime,uint256 startPrice,uint256 endPrice)";
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+pub fn order_typehash() -> [u8; 32] {
+    keccak256(ORDER_TYPE.as_bytes())
+}
+
+/// `keccak256(abi.encode(ORDER_TYPEHASH, ...fields))`, identical to the EVM `hashOrder`.
+/// Orders with a non-linear auction curve hash as the `ORDER_WITH_AUCTION_TYPE` extension.
+pub fn hash_order(order: &Order) -> [u8; 32] {
+    let receiver = order.receiver.as_ref().map(|addr| addr.as_str()).unwrap_or_default();
+    let fields = [
+        uint_word(order.salt.u128()),
+        address_word(order.maker.as_str()),
+        address_word(receiver),
+        address_word(&order.maker_asset),
+        address_word(&order.taker_asset),
+        uint_word(order.making_amount.u128()),
+        uint_word(order.taking_amount.u128()),
+        uint_word(order.deadline.into()),
+        uint_word(order.nonce.u128()),
+        uint_word(order.src_chain_id.into()),
+        uint_word(order.dst_chain_id.into()),
+        uint_word(order.auction_start_time.into()),
+        uint_word(order.auction_end_time.into()),
+        uint_word(order.start_price.u128()),
+        uint_word(order.end_price.u128()),
+    ]
+    .concat();
+    match hash_auction_details(&order.auction_details) {
+        Some(details) => keccak256(&[&order_with_auction_typehash()[..], &fields, &details].concat()),
+        None => keccak256(&[&order_typehash()[..], &fields].concat()),
+    }
+}
+
+/// Hex order hash (no `0x` prefix) used as the storage key across the contracts
+pub fn order_hash_hex(order: &Order) -> String {
+    hex::encode(hash_order(order))
+}
+
+/// ABI `uint256` word
+pub fn uint_word(value: u128) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[16..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// ABI `address` word for an address-like string.
+///
+/// `0x`-prefixed 20-byte hex is used as is and the empty string is the zero address.
+/// Anything else (bech32 accounts, bank denoms, CW20 contracts) maps to the last
+/// 20 bytes of `keccak256(utf8)`, so every chain derives the same EVM-side value.
+pub fn address_word(value: &str) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    if value.is_empty() {
+        return word;
+    }
+    match parse_evm_address(value) {
+        Some(address) => word[12..].copy_from_slice(&address),
+        None => word[12..].copy_from_slice(&keccak256(value.as_bytes())[12..]),
+    }
+    word
+}
+
+/// ABI `bytes32` word for a hash-like string.
+///
+/// 32-byte hex (with or without `0x`) is used as is, anything else maps to
+/// `keccak256(utf8)`.
+pub fn bytes32_word(value: &str) -> [u8; 32] {
+    let hex_part = value.strip_prefix("0x").unwrap_or(value);
+    let mut word = [0u8; 32];
+    match hex::decode_to_slice(hex_part, &mut word) {
+        Ok(()) => word,
+        Err(_) => keccak256(value.as_bytes()),
+    }
+}
+
+pub(crate) fn parse_evm_address(value: &str) -> Option<[u8; 20]> {
+    let hex_part = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X"))?;
+    let mut address = [0u8; 20];
+    hex::decode_to_slice(hex_part, &mut address).ok()?;
+    Some(address)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::order::{AuctionDetails, AuctionPoint};
+    use cosmwasm_std::{Addr, Uint128};
+
+    // Expected hashes come from `UniteLimitOrderProtocol.hashOrder`
+    pub(crate) fn evm_order() -> Order {
+        Order {
+            salt: Uint128::new(12345),
+            maker: Addr::unchecked("0x5B38Da6a701c568545dCfcB03FcB875f56beddC4"),
+            receiver: None,
+            maker_asset: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(),
+            taker_asset: "0x6B175474E89094C44Da98b954EedeAC495271d0F".to_string(),
+            making_amount: Uint128::new(1_000_000),
+            taking_amount: Uint128::new(990_000_000_000_000_000),
+            deadline: 1_700_003_600,
+            nonce: Uint128::zero(),
+            src_chain_id: 84532,
+            dst_chain_id: 5555,
+            auction_start_time: 1_700_000_000,
+            auction_end_time: 1_700_000_300,
+            start_price: Uint128::new(990_000_000_000_000_000),
+            end_price: Uint128::new(970_000_000_000_000_000),
+            auction_details: AuctionDetails::Linear,
+        }
+    }
+
+    pub(crate) fn cosmos_order() -> Order {
+        Order {
+            salt: Uint128::MAX,
+            maker: Addr::unchecked("osmo1qnk2n4nlkpw9xfqntladh74w6ujtulwn6dwq8z"),
+            receiver: Some(Addr::unchecked("osmo1c584m4lq25h83yp6ag8hh4htjr92d954kphp96")),
+            maker_asset: "uosmo".to_string(),
+            taker_asset: "ibc/498A0751C798A0D9A389AA3691123DADA57DAA4FE165D5C75894505B876BA6E4".to_string(),
+            making_amount: Uint128::new(5_000_000),
+            taking_amount: Uint128::new(5_000_000),
+            deadline: u64::MAX,
+            nonce: Uint128::new(7),
+            src_chain_id: 5555,
+            dst_chain_id: 1,
+            auction_start_time: 0,
+            auction_end_time: 0,
+            start_price: Uint128::new(1_000_000_000_000_000_000),
+            end_price: Uint128::new(1_000_000_000_000_000_000),
+            auction_details: AuctionDetails::Linear,
+        }
+    }
+
+    #[test]
+    fn typehash_matches_evm() {
+        assert_eq!(
+            hex::encode(order_typehash()),
+            "45782cf1039d7e8375398619d1ed6e07856e1b9616dda4d6c38bea52fae43b3d"
+        );
+    }
+
+    #[test]
+    fn hash_order_matches_evm_vectors() {
+        assert_eq!(
+            order_hash_hex(&evm_order()),
+            "4f67f30c1f4fe4ee2acc0044f4999f12dcae93fef99c9a9f3fa716b360c72f8d"
+        );
+        assert_eq!(
+            order_hash_hex(&cosmos_order()),
+            "91417e306a1d17e8353a81873741bf699b9b586a0c94fd187980ab923856cdae"
+        );
+    }
+
+    pub(crate) fn piecewise_order() -> Order {
+        Order {
+            auction_details: AuctionDetails::PiecewiseLinear {
+                initial_rate_bump: 500_000,
+                points: vec![
+                    AuctionPoint { delay: 60, rate_bump: 300_000 },
+                    AuctionPoint { delay: 120, rate_bump: 100_000 },
+                ],
+            },
+            ..evm_order()
+        }
+    }
+
+    #[test]
+    fn auction_curves_are_committed_to() {
+        // Expected hashes are the EIP-712 `hashStruct` of the `ORDER_WITH_AUCTION_TYPE` order
+        assert_eq!(
+            order_hash_hex(&piecewise_order()),
+            "edcd63e63733692d5993f920cf6f4271995be380a3172df28fbdaa8aaa36c780"
+        );
+        let mut order = evm_order();
+        let linear = order_hash_hex(&order);
+
+        order.auction_details = AuctionDetails::Exponential { half_life: 60 };
+        let exponential = order_hash_hex(&order);
+        assert_eq!(exponential, "1d36c69dcc01045700a4e06e65712cc29891858e1c975dc6aed330e8e40f69f3");
+        assert_ne!(exponential, linear);
+
+        order.auction_details = AuctionDetails::Exponential { half_life: 61 };
+        assert_ne!(order_hash_hex(&order), exponential);
+
+        // Legacy JSON without the field is a linear order with the EVM hash
+        let mut json = cosmwasm_std::to_json_vec(&evm_order()).unwrap();
+        json = String::from_utf8(json).unwrap().replace(r#","auction_details":"linear""#, "").into_bytes();
+        let legacy: Order = cosmwasm_std::from_json(&json).unwrap();
+        assert_eq!(order_hash_hex(&legacy), linear);
+    }
+
+    #[test]
+    fn non_evm_addresses_are_derived_from_keccak() {
+        assert_eq!(
+            hex::encode(&address_word("osmo1qnk2n4nlkpw9xfqntladh74w6ujtulwn6dwq8z")[12..]),
+            "307b2a5f0c2738f13f5749566122c55bbae222fc"
+        );
+        // Checksummed and lowercase EVM addresses encode the same
+        assert_eq!(
+            address_word("0x5B38Da6a701c568545dCfcB03FcB875f56beddC4"),
+            address_word("0x5b38da6a701c568545dcfcb03fcb875f56beddc4")
+        );
+        assert_eq!(address_word(""), [0u8; 32]);
+    }
+
+    #[test]
+    fn bytes32_words_accept_hex_or_derive_from_keccak() {
+        let hash = "4f67f30c1f4fe4ee2acc0044f4999f12dcae93fef99c9a9f3fa716b360c72f8d";
+        assert_eq!(hex::encode(bytes32_word(hash)), hash);
+        assert_eq!(bytes32_word(&format!("0x{hash}")), bytes32_word(hash));
+        assert_eq!(
+            hex::encode(bytes32_word("order")),
+            "21c0107378acb490e7190da71596effe409c128f08adcc5467b293f1f3a66431"
+        );
+    }
+}
@@ -0,0 +1,9 @@
+pub mod eip712;
+pub mod hash;
+pub mod order;
+pub mod signature;
+
+pub use crate::eip712::{order_digest, Eip712Domain};
+pub use crate::hash::{hash_order, order_hash_hex};
+pub use crate::order::{AuctionDetails, AuctionPoint, Order, RATE_BUMP_DENOMINATOR};
+pub use crate::signature::{verify_order_signature, OrderSignature};
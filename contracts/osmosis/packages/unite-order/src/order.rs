@@ -0,0 +1,58 @@
+use cosmwasm_std::{Addr, Uint128};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Cross-chain order, field-for-field with the EVM `IUniteOrder.Order`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Order {
+    pub salt: Uint128,
+    pub maker: Addr,
+    /// `None` means the maker receives the taker asset
+    pub receiver: Option<Addr>,
+    pub maker_asset: String,
+    pub taker_asset: String,
+    pub making_amount: Uint128,
+    pub taking_amount: Uint128,
+    pub deadline: u64,
+    pub nonce: Uint128,
+    pub src_chain_id: u64,
+    pub dst_chain_id: u64,
+    pub auction_start_time: u64,
+    pub auction_end_time: u64,
+    pub start_price: Uint128,  // Price per unit with 18 decimals precision
+    pub end_price: Uint128,    // Price per unit with 18 decimals precision
+    /// Price curve between `auction_start_time` and `auction_end_time`. Anything but the
+    /// default `Linear` is committed to by an extra word in the order hash.
+    #[serde(default)]
+    pub auction_details: AuctionDetails,
+}
+
+/// Shape of the Dutch auction price curve
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuctionDetails {
+    /// Straight decay from `start_price` to `end_price`, as on the EVM
+    #[default]
+    Linear,
+    /// 1inch-style curve of rate bumps over `end_price`, in units of `RATE_BUMP_DENOMINATOR`.
+    /// Starts at `initial_rate_bump`, interpolates through `points` and reaches zero at the
+    /// end of the auction. `start_price` is not used.
+    PiecewiseLinear {
+        initial_rate_bump: u32,
+        points: Vec<AuctionPoint>,
+    },
+    /// Decay from `start_price` towards `end_price` halving the gap every `half_life` seconds,
+    /// linearly between halvings rather than truly exponential, and down to `end_price` once
+    /// the auction ends
+    Exponential { half_life: u64 },
+}
+
+/// Rate bump reached `delay` seconds after the previous point, or the auction start
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuctionPoint {
+    pub delay: u32,
+    pub rate_bump: u32,
+}
+
+/// A rate bump of `RATE_BUMP_DENOMINATOR` doubles the price
+pub use unite_auction::RATE_BUMP_DENOMINATOR;
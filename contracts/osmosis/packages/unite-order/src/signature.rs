@@ -0,0 +1,182 @@
+use bech32::FromBase32;
+use cosmwasm_std::{Api, Binary};
+use ripemd::Ripemd160;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::eip712::{order_digest, Eip712Domain};
+use crate::hash::{hash_order, keccak256, order_hash_hex, parse_evm_address};
+use crate::order::Order;
+
+/// Maker authorization of an order
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderSignature {
+    /// Cosmos ADR-036 (`signArbitrary`) signature over the hex order hash, by a bech32 maker
+    Adr036 { pub_key: Binary, signature: Binary },
+    /// Ethereum `personal_sign` over the 32-byte order hash, by a `0x` maker
+    EthPersonalSign { signature: Binary },
+    /// Ethereum `eth_signTypedData_v4` over the order in `domain`, by a `0x` maker
+    Eip712 { domain: Eip712Domain, signature: Binary },
+}
+
+/// Returns whether `signature` was produced by `order.maker` for this exact order
+pub fn verify_order_signature(api: &dyn Api, order: &Order, signature: &OrderSignature) -> bool {
+    match signature {
+        OrderSignature::Adr036 { pub_key, signature } => {
+            verify_adr036(api, order, pub_key, signature)
+        }
+        OrderSignature::EthPersonalSign { signature } => {
+            let mut message = b"\x19Ethereum Signed Message:\n32".to_vec();
+            message.extend_from_slice(&hash_order(order));
+            verify_eth(api, order.maker.as_str(), &keccak256(&message), signature)
+        }
+        OrderSignature::Eip712 { domain, signature } => {
+            verify_eth(api, order.maker.as_str(), &order_digest(domain, order), signature)
+        }
+    }
+}
+
+/// Amino JSON sign doc of an ADR-036 `MsgSignData`, keys sorted as wallets serialize it
+pub fn adr036_sign_bytes(signer: &str, data: &[u8]) -> Vec<u8> {
+    format!(
+        r#"{{"account_number":"0","chain_id":"","fee":{{"amount":[],"gas":"0"}},"memo":"","msgs":[{{"type":"sign/MsgSignData","value":{{"data":"{}","signer":"{}"}}}}],"sequence":"0"}}"#,
+        Binary::from(data).to_base64(),
+        signer
+    )
+    .into_bytes()
+}
+
+fn verify_adr036(api: &dyn Api, order: &Order, pub_key: &Binary, signature: &Binary) -> bool {
+    // The key must belong to the maker: bech32 data is ripemd160(sha256(pubkey))
+    let maker_bytes = match bech32::decode(order.maker.as_str()) {
+        Ok((_, data, bech32::Variant::Bech32)) => Vec::<u8>::from_base32(&data).ok(),
+        _ => None,
+    };
+    let key_hash = Ripemd160::digest(Sha256::digest(pub_key.as_slice()));
+    if maker_bytes.as_deref() != Some(key_hash.as_slice()) {
+        return false;
+    }
+
+    let sign_bytes = adr036_sign_bytes(order.maker.as_str(), order_hash_hex(order).as_bytes());
+    let message_hash = Sha256::digest(sign_bytes);
+    api.secp256k1_verify(&message_hash, signature, pub_key)
+        .unwrap_or(false)
+}
+
+fn verify_eth(api: &dyn Api, maker: &str, digest: &[u8; 32], signature: &Binary) -> bool {
+    let Some(maker) = parse_evm_address(maker) else {
+        return false;
+    };
+    if signature.len() != 65 {
+        return false;
+    }
+    // Accept both `v` in {27, 28} and raw recovery ids
+    let recovery_param = match signature[64] {
+        v @ (27 | 28) => v - 27,
+        v @ (0 | 1) => v,
+        _ => return false,
+    };
+    match api.secp256k1_recover_pubkey(digest, &signature[..64], recovery_param) {
+        Ok(pub_key) if pub_key.len() == 65 => keccak256(&pub_key[1..])[12..] == maker,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockApi;
+    use cosmwasm_std::{Addr, Uint128};
+
+    use crate::order::AuctionDetails;
+
+    const ETH_MAKER: &str = "0x19E7E376E7C213B7E7e7e46cc70A5dD086DAff2A";
+    const COSMOS_MAKER: &str = "osmo12vfxp232rx0z9rzn0hay9jptagk8c86dm0cyvf";
+    const COSMOS_PUB_KEY: &str = "02466d7fcae563e5cb09a0d1870bb580344804617879a14949cf22285f1bae3f27";
+
+    fn order(maker: &str) -> Order {
+        Order {
+            salt: Uint128::new(42),
+            maker: Addr::unchecked(maker),
+            receiver: None,
+            maker_asset: "uosmo".to_string(),
+            taker_asset: "0x6B175474E89094C44Da98b954EedeAC495271d0F".to_string(),
+            making_amount: Uint128::new(1_000_000),
+            taking_amount: Uint128::new(1_000_000),
+            deadline: 1_700_003_600,
+            nonce: Uint128::zero(),
+            src_chain_id: 5555,
+            dst_chain_id: 84532,
+            auction_start_time: 1_700_000_000,
+            auction_end_time: 1_700_000_300,
+            start_price: Uint128::new(1_000_000_000_000_000_000),
+            end_price: Uint128::new(1_000_000_000_000_000_000),
+            auction_details: AuctionDetails::Linear,
+        }
+    }
+
+    fn binary(hex_str: &str) -> Binary {
+        Binary::from(hex::decode(hex_str).unwrap())
+    }
+
+    fn adr036() -> OrderSignature {
+        OrderSignature::Adr036 {
+            pub_key: binary(COSMOS_PUB_KEY),
+            signature: binary("438392bc3f13a32f2c0551adee673910648f3e478a4b85565ce555a756308ab13a07261889c6ca2b500d1a4affb556527d54e7663a98db413e45afbe453f3a7b"),
+        }
+    }
+
+    fn personal_sign() -> OrderSignature {
+        OrderSignature::EthPersonalSign {
+            signature: binary("c093cdc456220dc846e731ada3b040c8594aae5197e6ef355f13eda952a72bde296e80c0770da1a859a98971321b155a6394c6564179a3a3b5dfe0dcc23201a11c"),
+        }
+    }
+
+    fn eip712() -> OrderSignature {
+        OrderSignature::Eip712 {
+            domain: Eip712Domain::unite(84532, "0x734F3DDcE982B1b966B4234C53a1365D44980692"),
+            signature: binary("d636f07c02aaa9fd6bfb75c396f018e96f7534a4da2829f51bac3d7f3c78c33a075f9fe0f0b6b96f70434ec57d2ebdc1f88a05d017817bedaa1890bf21e8ab081c"),
+        }
+    }
+
+    #[test]
+    fn accepts_maker_signatures() {
+        let api = MockApi::default();
+        assert!(verify_order_signature(&api, &order(COSMOS_MAKER), &adr036()));
+        assert!(verify_order_signature(&api, &order(ETH_MAKER), &personal_sign()));
+        assert!(verify_order_signature(&api, &order(ETH_MAKER), &eip712()));
+    }
+
+    #[test]
+    fn rejects_tampered_orders() {
+        let api = MockApi::default();
+        let mut cosmos_order = order(COSMOS_MAKER);
+        cosmos_order.making_amount = Uint128::new(2_000_000);
+        assert!(!verify_order_signature(&api, &cosmos_order, &adr036()));
+
+        let mut eth_order = order(ETH_MAKER);
+        eth_order.taking_amount = Uint128::new(1);
+        assert!(!verify_order_signature(&api, &eth_order, &personal_sign()));
+        assert!(!verify_order_signature(&api, &eth_order, &eip712()));
+    }
+
+    #[test]
+    fn rejects_signatures_from_other_makers() {
+        let api = MockApi::default();
+        // Valid signature, but the maker field names someone else
+        assert!(!verify_order_signature(
+            &api,
+            &order("osmo1qnk2n4nlkpw9xfqntladh74w6ujtulwn6dwq8z"),
+            &adr036()
+        ));
+        assert!(!verify_order_signature(
+            &api,
+            &order("0x5B38Da6a701c568545dCfcB03FcB875f56beddC4"),
+            &personal_sign()
+        ));
+        // EVM signatures never authorize a bech32 maker
+        assert!(!verify_order_signature(&api, &order(COSMOS_MAKER), &eip712()));
+    }
+}
@@ -120,6 +120,7 @@ async function deployAll() {
     const escrowFactoryInit = {
       escrow_code_id: escrowUpload.codeId,
       order_protocol: orderProtocolResult.contractAddress,
+      safety_deposit_denom: "uosmo",
     };
 
     const escrowFactoryResult = await client.instantiate(
@@ -139,6 +140,7 @@ async function deployAll() {
     const resolverInit = {
       factory: escrowFactoryResult.contractAddress,
       order_protocol: orderProtocolResult.contractAddress,
+      safety_deposit_denom: "uosmo",
     };
 
     // Deploy 3 resolver contracts
@@ -82,6 +82,38 @@ async function setupCrossChainEnvironment() {
   
   console.log("\n[Setup] Step 3: Setting up resolver permissions...");
   
+  try {
+    await osmoClient.execute(
+      osmoAddress,
+      deployments.osmosis.contracts.escrowFactory.contractAddress,
+      {
+        add_resolver: {
+          resolver: deployments.osmosis.contracts.resolver.contractAddress,
+        },
+      },
+      "auto"
+    );
+    console.log("[Setup] ✅ Resolver allowed on Escrow Factory");
+  } catch (error: any) {
+    console.log("[Setup] ⚠️ Resolver registration failed:", error.message);
+  }
+
+  try {
+    await osmoClient.execute(
+      osmoAddress,
+      deployments.osmosis.contracts.orderProtocol.contractAddress,
+      {
+        add_resolver: {
+          resolver: deployments.osmosis.contracts.resolver.contractAddress,
+        },
+      },
+      "auto"
+    );
+    console.log("[Setup] ✅ Resolver allowed on Order Protocol");
+  } catch (error: any) {
+    console.log("[Setup] ⚠️ Resolver registration failed:", error.message);
+  }
+  
   const resolverAddresses = [osmoAddress];
   
   for (const resolverAddr of resolverAddresses) {
@@ -0,0 +1,38 @@
+//! Builds the contract wasm for the factory tests, which deploy real escrows from it.
+//! The wasm build itself runs this script too, and skips it.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=Cargo.toml");
+    println!("cargo:rerun-if-changed=../common");
+
+    if env::var("TARGET").unwrap().starts_with("wasm32") {
+        return;
+    }
+
+    let target_dir = PathBuf::from(env::var("OUT_DIR").unwrap()).join("wasm");
+    let status = Command::new(env::var("CARGO").unwrap())
+        .args(["build", "--release", "--target", "wasm32-unknown-unknown", "--target-dir"])
+        .arg(&target_dir)
+        .current_dir(env::var("CARGO_MANIFEST_DIR").unwrap())
+        // Settings of the outer build (clippy, host flags) do not apply to the wasm
+        .env_remove("RUSTC_WRAPPER")
+        .env_remove("RUSTC_WORKSPACE_WRAPPER")
+        .env_remove("RUSTFLAGS")
+        .env_remove("CARGO_ENCODED_RUSTFLAGS")
+        .env_remove("CARGO_TARGET_DIR")
+        .env_remove("CARGO_BUILD_TARGET")
+        .status()
+        .expect("failed to run cargo for the contract wasm");
+    assert!(
+        status.success(),
+        "building the contract wasm failed, is the target installed? rustup target add wasm32-unknown-unknown"
+    );
+
+    let wasm = target_dir.join("wasm32-unknown-unknown/release/unite_stellar.wasm");
+    println!("cargo:rustc-env=UNITE_STELLAR_WASM={}", wasm.display());
+}
@@ -0,0 +1,203 @@
+use soroban_sdk::{Bytes, BytesN, Env};
+
+use crate::types::{AuctionDetails, Order};
+
+// Must stay byte-identical to the EVM `UniteLimitOrderProtocol`
+pub const ORDER_TYPE: &[u8] = b"Order(uint256 salt,address maker,address receiver,address makerAsset,address takerAsset,uint256 makingAmount,uint256 takingAmount,uint256 deadline,uint256 nonce,uint256 srcChainId,uint256 dstChainId,uint256 auctionStartTime,uint256 auctionEndTime,uint256 startPrice,uint256 endPrice)";
+// Non-linear orders sign `Order` extended with their curve, as the CosmWasm `unite-order` crate.
+// `kind` is 1 for piecewise linear and 2 for exponential, unused fields are zero or empty.
+// The exponential curve halves the gap every `halfLife` seconds, linearly in between, and
+// reaches `endPrice` at `auctionEndTime`.
+pub const ORDER_WITH_AUCTION_TYPE: &[u8] = b"Order(uint256 salt,address maker,address receiver,address makerAsset,address takerAsset,uint256 makingAmount,uint256 takingAmount,uint256 deadline,uint256 nonce,uint256 srcChainId,uint256 dstChainId,uint256 auctionStartTime,uint256 auctionEndTime,uint256 startPrice,uint256 endPrice,AuctionDetails auctionDetails)AuctionDetails(uint8 kind,uint32 initialRateBump,AuctionPoint[] points,uint64 halfLife)AuctionPoint(uint32 delay,uint32 rateBump)";
+pub const AUCTION_DETAILS_TYPE: &[u8] = b"AuctionDetails(uint8 kind,uint32 initialRateBump,AuctionPoint[] points,uint64 halfLife)AuctionPoint(uint32 delay,uint32 rateBump)";
+pub const AUCTION_POINT_TYPE: &[u8] = b"AuctionPoint(uint32 delay,uint32 rateBump)";
+pub const DOMAIN_TYPE: &[u8] =
+    b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+pub const DOMAIN_NAME: &[u8] = b"UniteLimitOrderProtocol";
+pub const DOMAIN_VERSION: &[u8] = b"1";
+
+pub fn keccak256(env: &Env, data: &Bytes) -> BytesN<32> {
+    BytesN::from_array(env, &env.crypto().keccak256(data).to_array())
+}
+
+fn keccak256_slice(env: &Env, data: &[u8]) -> BytesN<32> {
+    keccak256(env, &Bytes::from_slice(env, data))
+}
+
+// ABI uint256 word
+fn uint_word(value: u128) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[16..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// `keccak256(abi.encode(ORDER_TYPEHASH, ...fields))`, identical to the EVM `hashOrder`.
+/// Address fields are already ABI words (20-byte address left-padded with zeros).
+/// Orders with a non-linear auction curve hash as the `ORDER_WITH_AUCTION_TYPE` extension.
+pub fn hash_order(env: &Env, order: &Order) -> BytesN<32> {
+    let details = hash_auction_details(env, &order.auction_details);
+    let order_type = if details.is_some() { ORDER_WITH_AUCTION_TYPE } else { ORDER_TYPE };
+    let mut data = Bytes::new(env);
+    data.append(&keccak256_slice(env, order_type).into());
+    data.extend_from_array(&uint_word(order.salt));
+    data.append(&order.maker.clone().into());
+    data.append(&order.receiver.clone().into());
+    data.append(&order.maker_asset.clone().into());
+    data.append(&order.taker_asset.clone().into());
+    data.extend_from_array(&uint_word(order.making_amount));
+    data.extend_from_array(&uint_word(order.taking_amount));
+    data.extend_from_array(&uint_word(order.deadline.into()));
+    data.extend_from_array(&uint_word(order.nonce));
+    data.extend_from_array(&uint_word(order.src_chain_id));
+    data.extend_from_array(&uint_word(order.dst_chain_id));
+    data.extend_from_array(&uint_word(order.auction_start_time.into()));
+    data.extend_from_array(&uint_word(order.auction_end_time.into()));
+    data.extend_from_array(&uint_word(order.start_price));
+    data.extend_from_array(&uint_word(order.end_price));
+    if let Some(details) = details {
+        data.append(&details.into());
+    }
+    keccak256(env, &data)
+}
+
+/// EIP-712 `hashStruct` of the `AuctionDetails` member, `None` for linear orders
+fn hash_auction_details(env: &Env, details: &AuctionDetails) -> Option<BytesN<32>> {
+    let mut point_hashes = Bytes::new(env);
+    let (kind, initial_rate_bump, half_life) = match details {
+        AuctionDetails::Linear => return None,
+        AuctionDetails::PiecewiseLinear(initial_rate_bump, points) => {
+            for point in points.iter() {
+                let mut data = Bytes::new(env);
+                data.append(&keccak256_slice(env, AUCTION_POINT_TYPE).into());
+                data.extend_from_array(&uint_word(point.delay.into()));
+                data.extend_from_array(&uint_word(point.rate_bump.into()));
+                point_hashes.append(&keccak256(env, &data).into());
+            }
+            (1, *initial_rate_bump, 0)
+        }
+        AuctionDetails::Exponential(half_life) => (2, 0, *half_life),
+    };
+    let mut data = Bytes::new(env);
+    data.append(&keccak256_slice(env, AUCTION_DETAILS_TYPE).into());
+    data.extend_from_array(&uint_word(kind));
+    data.extend_from_array(&uint_word(initial_rate_bump.into()));
+    data.append(&keccak256(env, &point_hashes).into());
+    data.extend_from_array(&uint_word(half_life.into()));
+    Some(keccak256(env, &data))
+}
+
+/// Separator of the `UniteLimitOrderProtocol` EIP-712 domain on `chain_id`
+pub fn domain_separator(env: &Env, chain_id: u128, verifying_contract: &BytesN<32>) -> BytesN<32> {
+    let mut data = Bytes::new(env);
+    data.append(&keccak256_slice(env, DOMAIN_TYPE).into());
+    data.append(&keccak256_slice(env, DOMAIN_NAME).into());
+    data.append(&keccak256_slice(env, DOMAIN_VERSION).into());
+    data.extend_from_array(&uint_word(chain_id));
+    data.append(&verifying_contract.clone().into());
+    keccak256(env, &data)
+}
+
+/// `keccak256("\x19\x01" || domainSeparator || structHash)`
+pub fn typed_data_hash(env: &Env, domain_separator: &BytesN<32>, struct_hash: &BytesN<32>) -> BytesN<32> {
+    let mut data = Bytes::from_array(env, b"\x19\x01");
+    data.append(&domain_separator.clone().into());
+    data.append(&struct_hash.clone().into());
+    keccak256(env, &data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::AuctionPoint;
+    use soroban_sdk::vec;
+
+    fn word(env: &Env, address: [u8; 20]) -> BytesN<32> {
+        let mut word = [0u8; 32];
+        word[12..].copy_from_slice(&address);
+        BytesN::from_array(env, &word)
+    }
+
+    fn hex32(env: &Env, hex: &str) -> BytesN<32> {
+        let mut out = [0u8; 32];
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        BytesN::from_array(env, &out)
+    }
+
+    fn hex20(hex: &str) -> [u8; 20] {
+        let mut out = [0u8; 20];
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        out
+    }
+
+    // Same vector as the CosmWasm `unite-order` crate, pinned to the EVM contract
+    fn evm_order(env: &Env) -> Order {
+        Order {
+            salt: 12345,
+            maker: word(env, hex20("5b38da6a701c568545dcfcb03fcb875f56beddc4")),
+            receiver: word(env, [0u8; 20]),
+            maker_asset: word(env, hex20("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48")),
+            taker_asset: word(env, hex20("6b175474e89094c44da98b954eedeac495271d0f")),
+            making_amount: 1_000_000,
+            taking_amount: 990_000_000_000_000_000,
+            deadline: 1_700_003_600,
+            nonce: 0,
+            src_chain_id: 84532,
+            dst_chain_id: 5555,
+            auction_start_time: 1_700_000_000,
+            auction_end_time: 1_700_000_300,
+            start_price: 990_000_000_000_000_000,
+            end_price: 970_000_000_000_000_000,
+            auction_details: AuctionDetails::Linear,
+        }
+    }
+
+    #[test]
+    fn test_hash_order_matches_evm() {
+        let env = Env::default();
+        assert_eq!(
+            hash_order(&env, &evm_order(&env)),
+            hex32(&env, "4f67f30c1f4fe4ee2acc0044f4999f12dcae93fef99c9a9f3fa716b360c72f8d")
+        );
+    }
+
+    #[test]
+    fn test_typed_data_hash_matches_evm() {
+        let env = Env::default();
+        // UniteLimitOrderProtocol on Base Sepolia
+        let verifying_contract = word(&env, hex20("734f3ddce982b1b966b4234c53a1365d44980692"));
+        let separator = domain_separator(&env, 84532, &verifying_contract);
+        assert_eq!(
+            separator,
+            hex32(&env, "394a9a236e2b3ad92a890dca07941933d9e30e2c1f810e8fb9fd412cf22d1a2b")
+        );
+        assert_eq!(
+            typed_data_hash(&env, &separator, &hash_order(&env, &evm_order(&env))),
+            hex32(&env, "0aa0f85746539df095afd7e5dd3f681c333964aeb18244178f8c53d12d4fd029")
+        );
+    }
+
+    #[test]
+    fn test_non_linear_orders_match_cosmwasm() {
+        let env = Env::default();
+        let mut order = evm_order(&env);
+        let points = vec![
+            &env,
+            AuctionPoint { delay: 60, rate_bump: 300_000 },
+            AuctionPoint { delay: 120, rate_bump: 100_000 },
+        ];
+        order.auction_details = AuctionDetails::PiecewiseLinear(500_000, points);
+        assert_eq!(
+            hash_order(&env, &order),
+            hex32(&env, "edcd63e63733692d5993f920cf6f4271995be380a3172df28fbdaa8aaa36c780")
+        );
+        order.auction_details = AuctionDetails::Exponential(60);
+        assert_eq!(
+            hash_order(&env, &order),
+            hex32(&env, "1d36c69dcc01045700a4e06e65712cc29891858e1c975dc6aed330e8e40f69f3")
+        );
+    }
+}
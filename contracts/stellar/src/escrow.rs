@@ -1,9 +1,14 @@
 use soroban_sdk::{
-    contract, contractimpl, token, Address, BytesN, Env, Map, Symbol, Vec,
+    contract, contractimpl, contracttype, token, Address, BytesN, Env, Symbol, Vec, U256,
     symbol_short, panic_with_error
 };
 
-use crate::types::{Error, State, Immutables, Timelocks, CALLER_REWARD_PERCENTAGE};
+use unite_timelocks::{Stage, Timelocks};
+
+use crate::factory::UniteEscrowFactoryClient;
+use crate::types::{
+    ttl_until, unpack_timelocks, Error, State, Immutables, CALLER_REWARD_PERCENTAGE, DAY_IN_LEDGERS,
+};
 
 // Storage keys
 const STATE: Symbol = symbol_short!("state");
@@ -19,21 +24,40 @@ const IS_SOURCE: Symbol = symbol_short!("issrc");
 const SRC_CANCEL_TS: Symbol = symbol_short!("srcts");
 const FACTORY: Symbol = symbol_short!("factory");
 const DEPLOYED_AT: Symbol = symbol_short!("depat");
-const RESOLVERS: Symbol = symbol_short!("rslvrs");
-const RESOLVER_AMOUNTS: Symbol = symbol_short!("rslamt");
-const RESOLVER_DEPOSITS: Symbol = symbol_short!("rsldep");
+const RESOLVER_COUNT: Symbol = symbol_short!("rslcnt");
 const TOTAL_PARTIAL: Symbol = symbol_short!("totpar");
+const TOTAL_DEPOSITS: Symbol = symbol_short!("totdep");
 const FUNDS_DISTRIBUTED: Symbol = symbol_short!("fundist");
+const NATIVE_TOKEN: Symbol = symbol_short!("native");
+
+// Per-resolver entries live in persistent storage so the instance stays small
+#[contracttype(export = false)]
+#[derive(Clone)]
+enum DataKey {
+    ResolverAt(u32),
+    Resolver(Address),
+}
+
+#[contracttype(export = false)]
+#[derive(Clone)]
+struct ResolverFill {
+    partial_amount: i128,
+    safety_deposit: i128,
+}
 
 #[contract]
 pub struct UniteEscrow;
 
 #[contractimpl]
 impl UniteEscrow {
-    pub fn initialize(env: Env, immutables: Immutables, is_source: bool) {
+    /// `native_token` is the Stellar Asset Contract of XLM, in which safety deposits are held.
+    /// `factory` is the deploying `UniteEscrowFactory`; it must authorize the call and its
+    /// resolver allow-list decides who may join the escrow.
+    pub fn initialize(env: Env, immutables: Immutables, is_source: bool, native_token: Address, factory: Address) {
         if env.storage().instance().has(&ORDER_HASH) {
             panic_with_error!(&env, Error::AlreadyInitialized);
         }
+        factory.require_auth();
 
         let current_time = env.ledger().timestamp();
         
@@ -47,21 +71,28 @@ impl UniteEscrow {
         env.storage().instance().set(&TIMELOCKS, &immutables.timelocks);
         env.storage().instance().set(&IS_SOURCE, &is_source);
         env.storage().instance().set(&STATE, &State::Active);
-        env.storage().instance().set(&FACTORY, &env.current_contract_address());
+        env.storage().instance().set(&FACTORY, &factory);
         env.storage().instance().set(&DEPLOYED_AT, &current_time);
-        env.storage().instance().set(&RESOLVERS, &Vec::<Address>::new(&env));
-        env.storage().instance().set(&RESOLVER_AMOUNTS, &Map::<Address, i128>::new(&env));
-        env.storage().instance().set(&RESOLVER_DEPOSITS, &Map::<Address, i128>::new(&env));
+        env.storage().instance().set(&RESOLVER_COUNT, &0u32);
         env.storage().instance().set(&TOTAL_PARTIAL, &0i128);
+        env.storage().instance().set(&TOTAL_DEPOSITS, &0i128);
         env.storage().instance().set(&FUNDS_DISTRIBUTED, &false);
+        env.storage().instance().set(&NATIVE_TOKEN, &native_token);
         
-        env.storage().instance().extend_ttl(100, 10000);
+        Self::extend_storage(&env);
     }
 
-    pub fn initialize_dst(env: Env, immutables: Immutables, src_cancellation_timestamp: u64) {
+    pub fn initialize_dst(
+        env: Env,
+        immutables: Immutables,
+        src_cancellation_timestamp: u64,
+        native_token: Address,
+        factory: Address
+    ) {
         if env.storage().instance().has(&ORDER_HASH) {
             panic_with_error!(&env, Error::AlreadyInitialized);
         }
+        factory.require_auth();
 
         let current_time = env.ledger().timestamp();
         
@@ -76,50 +107,67 @@ impl UniteEscrow {
         env.storage().instance().set(&IS_SOURCE, &false);
         env.storage().instance().set(&SRC_CANCEL_TS, &src_cancellation_timestamp);
         env.storage().instance().set(&STATE, &State::Active);
-        env.storage().instance().set(&FACTORY, &env.current_contract_address());
+        env.storage().instance().set(&FACTORY, &factory);
         env.storage().instance().set(&DEPLOYED_AT, &current_time);
-        env.storage().instance().set(&RESOLVERS, &Vec::<Address>::new(&env));
-        env.storage().instance().set(&RESOLVER_AMOUNTS, &Map::<Address, i128>::new(&env));
-        env.storage().instance().set(&RESOLVER_DEPOSITS, &Map::<Address, i128>::new(&env));
+        env.storage().instance().set(&RESOLVER_COUNT, &0u32);
         env.storage().instance().set(&TOTAL_PARTIAL, &0i128);
+        env.storage().instance().set(&TOTAL_DEPOSITS, &0i128);
         env.storage().instance().set(&FUNDS_DISTRIBUTED, &false);
+        env.storage().instance().set(&NATIVE_TOKEN, &native_token);
         
-        env.storage().instance().extend_ttl(100, 10000);
+        Self::extend_storage(&env);
     }
 
-    pub fn add_resolver_safety_deposit(env: Env, resolver: Address, partial_amount: i128) {
+    /// Registers `caller` as a resolver filling `partial_amount` of the order. Only resolvers
+    /// allow-listed on the factory may join, while the escrow is active and has room left.
+    pub fn add_resolver_safety_deposit(env: Env, caller: Address, partial_amount: i128) {
+        caller.require_auth();
+        let resolver = caller;
+
         if !env.storage().instance().has(&ORDER_HASH) {
             panic_with_error!(&env, Error::NotInitialized);
         }
+        Self::check_active(&env);
 
-        let mut resolver_amounts: Map<Address, i128> = env.storage().instance().get(&RESOLVER_AMOUNTS).unwrap();
-        if resolver_amounts.contains_key(resolver.clone()) {
+        let factory: Address = env.storage().instance().get(&FACTORY).unwrap();
+        if !UniteEscrowFactoryClient::new(&env, &factory).is_resolver(&resolver) {
+            panic_with_error!(&env, Error::NotResolver);
+        }
+
+        if Self::resolver_fill(&env, &resolver).is_some() {
             panic_with_error!(&env, Error::ResolverAlreadyAdded);
         }
 
-        if partial_amount <= 0 {
+        // The fills together never exceed the escrowed amount
+        let amount: i128 = env.storage().instance().get(&AMOUNT).unwrap();
+        let total_partial: i128 = env.storage().instance().get(&TOTAL_PARTIAL).unwrap();
+        if partial_amount <= 0 || partial_amount > amount - total_partial {
             panic_with_error!(&env, Error::InvalidAmount);
         }
 
         let safety_deposit: i128 = env.storage().instance().get(&SAFETY_DEPOSIT).unwrap();
+        let resolver_count: u32 = env.storage().instance().get(&RESOLVER_COUNT).unwrap();
+        let total_deposits: i128 = env.storage().instance().get(&TOTAL_DEPOSITS).unwrap();
 
-        let mut resolvers: Vec<Address> = env.storage().instance().get(&RESOLVERS).unwrap();
-        let mut resolver_deposits: Map<Address, i128> = env.storage().instance().get(&RESOLVER_DEPOSITS).unwrap();
-        let total_partial: i128 = env.storage().instance().get(&TOTAL_PARTIAL).unwrap();
+        let native_token: Address = env.storage().instance().get(&NATIVE_TOKEN).unwrap();
+        token::Client::new(&env, &native_token).transfer(&resolver, &env.current_contract_address(), &safety_deposit);
 
-        resolvers.push_back(resolver.clone());
-        resolver_amounts.set(resolver.clone(), partial_amount);
-        resolver_deposits.set(resolver.clone(), safety_deposit);
-        
-        env.storage().instance().set(&RESOLVERS, &resolvers);
-        env.storage().instance().set(&RESOLVER_AMOUNTS, &resolver_amounts);
-        env.storage().instance().set(&RESOLVER_DEPOSITS, &resolver_deposits);
+        let index_key = DataKey::ResolverAt(resolver_count);
+        let fill_key = DataKey::Resolver(resolver.clone());
+        env.storage().persistent().set(&index_key, &resolver);
+        env.storage().persistent().set(&fill_key, &ResolverFill { partial_amount, safety_deposit });
+        Self::extend_persistent(&env, &index_key);
+        Self::extend_persistent(&env, &fill_key);
+
+        env.storage().instance().set(&RESOLVER_COUNT, &(resolver_count + 1));
         env.storage().instance().set(&TOTAL_PARTIAL, &(total_partial + partial_amount));
+        env.storage().instance().set(&TOTAL_DEPOSITS, &(total_deposits + safety_deposit));
         
-        env.storage().instance().extend_ttl(100, 10000);
+        Self::extend_storage(&env);
     }
 
-    pub fn withdraw_with_secret(env: Env, secret: BytesN<32>, immutables: Immutables) {
+    pub fn withdraw_with_secret(env: Env, caller: Address, secret: BytesN<32>, immutables: Immutables) {
+        caller.require_auth();
         Self::check_active(&env);
         
         let hashlock: BytesN<32> = env.storage().instance().get(&HASHLOCK).unwrap();
@@ -135,6 +183,7 @@ impl UniteEscrow {
         }
 
         Self::verify_immutables(&env, &immutables);
+        Self::check_withdrawal(&env, &caller);
 
         let funds_distributed: bool = env.storage().instance().get(&FUNDS_DISTRIBUTED).unwrap();
         if funds_distributed {
@@ -147,46 +196,46 @@ impl UniteEscrow {
 
         if !is_source {
             let total_partial: i128 = env.storage().instance().get(&TOTAL_PARTIAL).unwrap();
-            let current_balance = token_client.balance(&env.current_contract_address());
+            let current_balance = Self::escrowed_balance(&env, &token_client);
             if current_balance < total_partial {
                 panic_with_error!(&env, Error::NotAllResolversDeposited);
             }
         }
 
-        let caller_reward = Self::calculate_caller_reward(&env);
+        let caller_reward = Self::calculate_caller_reward(&env, &caller);
 
         env.storage().instance().set(&FUNDS_DISTRIBUTED, &true);
 
         if is_source {
-            Self::distribute_source_funds(&env, caller_reward);
+            Self::distribute_source_funds(&env, &caller, caller_reward);
         } else {
-            Self::distribute_destination_funds(&env, caller_reward);
+            Self::distribute_destination_funds(&env, &caller, caller_reward);
         }
 
         env.storage().instance().set(&STATE, &State::Withdrawn);
-        env.storage().instance().extend_ttl(100, 10000);
+        Self::extend_storage(&env);
     }
 
-    pub fn cancel(env: Env, immutables: Immutables) {
+    pub fn cancel(env: Env, caller: Address, immutables: Immutables) {
+        caller.require_auth();
         Self::check_active(&env);
         Self::verify_immutables(&env, &immutables);
 
         let current_time = env.ledger().timestamp();
-        let deployed_at: u64 = env.storage().instance().get(&DEPLOYED_AT).unwrap();
-        let timelocks_encoded: u128 = env.storage().instance().get(&TIMELOCKS).unwrap();
-        let timelocks = Timelocks::decode(timelocks_encoded, deployed_at);
+        let timelocks = Self::timelocks(&env);
         let is_source: bool = env.storage().instance().get(&IS_SOURCE).unwrap();
         let maker: Address = env.storage().instance().get(&MAKER).unwrap();
 
         if is_source {
-            let cancellation_time = deployed_at + timelocks.src_cancellation;
-            let public_cancellation_time = deployed_at + timelocks.src_public_cancellation;
-
-            if current_time < cancellation_time {
+            if current_time < timelocks.get(Stage::SrcCancellation) {
                 panic_with_error!(&env, Error::InvalidTime);
             }
 
-            if current_time < public_cancellation_time && env.current_contract_address() != maker {
+            // Private cancellation is left to the maker and the takers
+            if current_time < timelocks.get(Stage::SrcPublicCancellation)
+                && caller != maker
+                && !Self::is_taker(&env, &caller)
+            {
                 panic_with_error!(&env, Error::InvalidCaller);
             }
         } else {
@@ -195,23 +244,57 @@ impl UniteEscrow {
                 panic_with_error!(&env, Error::InvalidTime);
             }
 
-            let cancellation_time = deployed_at + timelocks.dst_cancellation;
-            if current_time < cancellation_time {
+            if current_time < timelocks.get(Stage::DstCancellation) {
                 panic_with_error!(&env, Error::InvalidTime);
             }
+
+            // Destination escrows have no public cancellation
+            if !Self::is_taker(&env, &caller) {
+                panic_with_error!(&env, Error::InvalidCaller);
+            }
         }
 
         env.storage().instance().set(&STATE, &State::Cancelled);
 
         let token: Address = env.storage().instance().get(&TOKEN).unwrap();
         let token_client = token::Client::new(&env, &token);
-        let contract_balance = token_client.balance(&env.current_contract_address());
+        let mut contract_balance = Self::escrowed_balance(&env, &token_client);
+
+        if is_source {
+            if contract_balance > 0 {
+                token_client.transfer(&env.current_contract_address(), &maker, &contract_balance);
+            }
+        } else {
+            // Destination tokens came from the resolvers, each gets its fill back
+            for (resolver, fill) in Self::resolvers(&env).iter() {
+                let refund = fill.partial_amount.min(contract_balance);
+                if refund > 0 {
+                    token_client.transfer(&env.current_contract_address(), &resolver, &refund);
+                    contract_balance -= refund;
+                }
+            }
+        }
 
-        if contract_balance > 0 {
-            token_client.transfer(&env.current_contract_address(), &maker, &contract_balance);
+        let caller_reward = Self::calculate_caller_reward(&env, &caller);
+        Self::return_safety_deposits(&env, &caller, caller_reward);
+
+        Self::extend_storage(&env);
+    }
+
+    /// Bumps the TTL of the escrow and its resolver entries. Anyone may call it to keep an
+    /// escrow from being archived before its funds are released.
+    pub fn extend(env: Env) {
+        if !env.storage().instance().has(&ORDER_HASH) {
+            panic_with_error!(&env, Error::NotInitialized);
         }
 
-        env.storage().instance().extend_ttl(100, 10000);
+        Self::extend_storage(&env);
+        let resolver_count: u32 = env.storage().instance().get(&RESOLVER_COUNT).unwrap();
+        for index in 0..resolver_count {
+            let resolver: Address = env.storage().persistent().get(&DataKey::ResolverAt(index)).unwrap();
+            Self::extend_persistent(&env, &DataKey::ResolverAt(index));
+            Self::extend_persistent(&env, &DataKey::Resolver(resolver));
+        }
     }
 
     // Helper functions
@@ -223,6 +306,76 @@ impl UniteEscrow {
         }
     }
 
+    // Stage timestamps count from the escrow deployment, like `TimelocksLib.setDeployedAt`
+    fn timelocks(env: &Env) -> Timelocks {
+        let deployed_at: u64 = env.storage().instance().get(&DEPLOYED_AT).unwrap();
+        let packed: U256 = env.storage().instance().get(&TIMELOCKS).unwrap();
+        let deployed_at = u32::try_from(deployed_at).unwrap_or_else(|_| panic_with_error!(env, Error::InvalidTime));
+        unpack_timelocks(&packed).with_deployed_at(deployed_at)
+    }
+
+    // Withdrawal window: the takers first, anyone once public withdrawal opens
+    fn check_withdrawal(env: &Env, caller: &Address) {
+        let current_time = env.ledger().timestamp();
+        let timelocks = Self::timelocks(env);
+        let is_source: bool = env.storage().instance().get(&IS_SOURCE).unwrap();
+
+        let (withdrawal, public_withdrawal, cancellation) = if is_source {
+            (Stage::SrcWithdrawal, Stage::SrcPublicWithdrawal, Stage::SrcCancellation)
+        } else {
+            (Stage::DstWithdrawal, Stage::DstPublicWithdrawal, Stage::DstCancellation)
+        };
+
+        if current_time < timelocks.get(withdrawal) || current_time >= timelocks.get(cancellation) {
+            panic_with_error!(env, Error::InvalidTime);
+        }
+
+        if current_time < timelocks.get(public_withdrawal) && !Self::is_taker(env, caller) {
+            panic_with_error!(env, Error::InvalidCaller);
+        }
+    }
+
+    // The order taker or one of the resolvers filling it
+    fn is_taker(env: &Env, address: &Address) -> bool {
+        let taker: Address = env.storage().instance().get(&TAKER).unwrap();
+        *address == taker || Self::resolver_fill(env, address).is_some()
+    }
+
+    fn resolver_fill(env: &Env, resolver: &Address) -> Option<ResolverFill> {
+        env.storage().persistent().get(&DataKey::Resolver(resolver.clone()))
+    }
+
+    // Resolvers in the order they joined
+    fn resolvers(env: &Env) -> Vec<(Address, ResolverFill)> {
+        let resolver_count: u32 = env.storage().instance().get(&RESOLVER_COUNT).unwrap();
+        let mut resolvers = Vec::new(env);
+        for index in 0..resolver_count {
+            let resolver: Address = env.storage().persistent().get(&DataKey::ResolverAt(index)).unwrap();
+            let fill = Self::resolver_fill(env, &resolver).unwrap();
+            resolvers.push_back((resolver, fill));
+        }
+        resolvers
+    }
+
+    // Ledgers the escrow must stay live for: until its last stage opens, plus a week to act on it
+    fn required_ttl(env: &Env) -> u32 {
+        let timelocks = Self::timelocks(env);
+        let is_source: bool = env.storage().instance().get(&IS_SOURCE).unwrap();
+        let last_stage = if is_source { Stage::SrcPublicCancellation } else { Stage::DstCancellation };
+
+        ttl_until(env, timelocks.get(last_stage))
+    }
+
+    fn extend_storage(env: &Env) {
+        let ttl = Self::required_ttl(env);
+        env.storage().instance().extend_ttl(ttl.saturating_sub(DAY_IN_LEDGERS), ttl);
+    }
+
+    fn extend_persistent(env: &Env, key: &DataKey) {
+        let ttl = Self::required_ttl(env);
+        env.storage().persistent().extend_ttl(key, ttl.saturating_sub(DAY_IN_LEDGERS), ttl);
+    }
+
     fn verify_immutables(env: &Env, immutables: &Immutables) {
         let stored_hash: BytesN<32> = env.storage().instance().get(&ORDER_HASH).unwrap();
         let stored_hashlock: BytesN<32> = env.storage().instance().get(&HASHLOCK).unwrap();
@@ -243,84 +396,70 @@ impl UniteEscrow {
         }
     }
 
-    fn calculate_caller_reward(env: &Env) -> i128 {
-        let current_time = env.ledger().timestamp();
-        let deployed_at: u64 = env.storage().instance().get(&DEPLOYED_AT).unwrap();
-        let timelocks_encoded: u128 = env.storage().instance().get(&TIMELOCKS).unwrap();
-        let timelocks = Timelocks::decode(timelocks_encoded, deployed_at);
-        let is_source: bool = env.storage().instance().get(&IS_SOURCE).unwrap();
+    // Public callers outside the order earn a share of the safety deposits. Outsiders can
+    // only get past the caller checks once the public stages open.
+    fn calculate_caller_reward(env: &Env, caller: &Address) -> i128 {
         let maker: Address = env.storage().instance().get(&MAKER).unwrap();
+        if *caller == maker || Self::is_taker(env, caller) {
+            return 0;
+        }
 
-        let is_after_time_limit = if is_source {
-            current_time >= deployed_at + timelocks.src_public_withdrawal
-        } else {
-            current_time >= deployed_at + timelocks.dst_public_withdrawal
-        };
-
-        if is_after_time_limit && env.current_contract_address() != maker {
-            let resolvers: Vec<Address> = env.storage().instance().get(&RESOLVERS).unwrap();
-            let mut is_resolver = false;
-            for resolver in resolvers.iter() {
-                if resolver == env.current_contract_address() {
-                    is_resolver = true;
-                    break;
-                }
-            }
-
-            if !is_resolver {
-                let resolver_deposits: Map<Address, i128> = env.storage().instance().get(&RESOLVER_DEPOSITS).unwrap();
-                let mut total_deposits = 0i128;
-                for (_, deposit) in resolver_deposits.iter() {
-                    total_deposits += deposit;
-                }
-                return (total_deposits * CALLER_REWARD_PERCENTAGE as i128) / 100;
-            }
+        let mut caller_reward = 0i128;
+        for (_, fill) in Self::resolvers(env).iter() {
+            caller_reward += Self::reward_share(fill.safety_deposit);
         }
+        caller_reward
+    }
 
-        0
+    fn reward_share(deposit: i128) -> i128 {
+        (deposit * CALLER_REWARD_PERCENTAGE as i128) / 100
     }
 
-    fn distribute_source_funds(env: &Env, caller_reward: i128) {
-        let resolvers: Vec<Address> = env.storage().instance().get(&RESOLVERS).unwrap();
-        let resolver_amounts: Map<Address, i128> = env.storage().instance().get(&RESOLVER_AMOUNTS).unwrap();
-        let resolver_deposits: Map<Address, i128> = env.storage().instance().get(&RESOLVER_DEPOSITS).unwrap();
+    fn distribute_source_funds(env: &Env, caller: &Address, caller_reward: i128) {
         let token: Address = env.storage().instance().get(&TOKEN).unwrap();
-        let token_client = token::Client::new(&env, &token);
-
-        for resolver in resolvers.iter() {
-            let amount = resolver_amounts.get(resolver.clone()).unwrap();
-            let deposit = resolver_deposits.get(resolver.clone()).unwrap();
+        let token_client = token::Client::new(env, &token);
 
-            let _actual_deposit = if caller_reward > 0 {
-                let deduction = (deposit * CALLER_REWARD_PERCENTAGE as i128) / 100;
-                deposit - deduction
-            } else {
-                deposit
-            };
-
-            token_client.transfer(&env.current_contract_address(), &resolver, &amount);
+        for (resolver, fill) in Self::resolvers(env).iter() {
+            token_client.transfer(&env.current_contract_address(), &resolver, &fill.partial_amount);
         }
+
+        Self::return_safety_deposits(env, caller, caller_reward);
     }
 
-    fn distribute_destination_funds(env: &Env, caller_reward: i128) {
+    fn distribute_destination_funds(env: &Env, caller: &Address, caller_reward: i128) {
         let maker: Address = env.storage().instance().get(&MAKER).unwrap();
         let total_partial: i128 = env.storage().instance().get(&TOTAL_PARTIAL).unwrap();
         let token: Address = env.storage().instance().get(&TOKEN).unwrap();
-        let token_client = token::Client::new(&env, &token);
+        let token_client = token::Client::new(env, &token);
 
         token_client.transfer(&env.current_contract_address(), &maker, &total_partial);
 
-        let resolvers: Vec<Address> = env.storage().instance().get(&RESOLVERS).unwrap();
-        let resolver_deposits: Map<Address, i128> = env.storage().instance().get(&RESOLVER_DEPOSITS).unwrap();
+        Self::return_safety_deposits(env, caller, caller_reward);
+    }
 
-        for resolver in resolvers.iter() {
-            let deposit = resolver_deposits.get(resolver.clone()).unwrap();
-            let _actual_deposit = if caller_reward > 0 {
-                let deduction = (deposit * CALLER_REWARD_PERCENTAGE as i128) / 100;
-                deposit - deduction
-            } else {
-                deposit
-            };
+    // Balance of the escrowed token, not counting safety deposits when the order swaps XLM
+    fn escrowed_balance(env: &Env, token_client: &token::Client) -> i128 {
+        let balance = token_client.balance(&env.current_contract_address());
+        let native_token: Address = env.storage().instance().get(&NATIVE_TOKEN).unwrap();
+        if token_client.address != native_token {
+            return balance;
+        }
+
+        let total_deposits: i128 = env.storage().instance().get(&TOTAL_DEPOSITS).unwrap();
+        balance - total_deposits
+    }
+
+    // Pays every resolver its safety deposit back, less its share of `caller_reward`
+    fn return_safety_deposits(env: &Env, caller: &Address, caller_reward: i128) {
+        let native_token: Address = env.storage().instance().get(&NATIVE_TOKEN).unwrap();
+        let native_client = token::Client::new(env, &native_token);
+        for (resolver, fill) in Self::resolvers(env).iter() {
+            let deduction = if caller_reward > 0 { Self::reward_share(fill.safety_deposit) } else { 0 };
+            native_client.transfer(&env.current_contract_address(), &resolver, &(fill.safety_deposit - deduction));
+        }
+
+        if caller_reward > 0 {
+            native_client.transfer(&env.current_contract_address(), caller, &caller_reward);
         }
     }
 
@@ -333,13 +472,379 @@ impl UniteEscrow {
     }
 
     pub fn get_resolver_info(env: Env, resolver: Address) -> (i128, i128, bool) {
-        let resolver_amounts: Map<Address, i128> = env.storage().instance().get(&RESOLVER_AMOUNTS).unwrap_or(Map::new(&env));
-        let resolver_deposits: Map<Address, i128> = env.storage().instance().get(&RESOLVER_DEPOSITS).unwrap_or(Map::new(&env));
         let funds_distributed: bool = env.storage().instance().get(&FUNDS_DISTRIBUTED).unwrap_or(false);
 
-        let amount = resolver_amounts.get(resolver.clone()).unwrap_or(0);
-        let deposit = resolver_deposits.get(resolver.clone()).unwrap_or(0);
+        match Self::resolver_fill(&env, &resolver) {
+            Some(fill) => (fill.partial_amount, fill.safety_deposit, funds_distributed),
+            None => (0, 0, funds_distributed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::*;
+    use crate::factory::UniteEscrowFactory;
+    use crate::types::{INSTANCE_BUMP_AMOUNT, LEDGER_CLOSE_SECONDS};
+    use soroban_sdk::testutils::storage::{Instance as _, Persistent as _};
+    use soroban_sdk::testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation, Ledger};
+    use soroban_sdk::{token::StellarAssetClient, Bytes, IntoVal};
+
+    const DEPLOYED_AT: u64 = 1_000;
+    const AMOUNT: i128 = 1_000;
+    const SAFETY_DEPOSIT: i128 = 100;
+    const SECRET: [u8; 32] = [7u8; 32];
+
+    struct Setup<'a> {
+        env: Env,
+        escrow: UniteEscrowClient<'a>,
+        token: token::Client<'a>,
+        native: token::Client<'a>,
+        factory: UniteEscrowFactoryClient<'a>,
+        immutables: Immutables,
+        maker: Address,
+        resolver: Address,
+        is_source: bool,
+    }
+
+    impl<'a> Setup<'a> {
+        fn new(is_source: bool) -> Self {
+            // Withdrawal at +10, public withdrawal at +100, cancellation at +200, public cancellation at +300
+            Self::with_timelocks(is_source, [10, 100, 200, 300, 10, 100, 200])
+        }
+
+        fn with_timelocks(is_source: bool, offsets: [u32; 7]) -> Self {
+            let env = Env::default();
+            env.mock_all_auths();
+            env.ledger().set_timestamp(DEPLOYED_AT);
+
+            let maker = Address::generate(&env);
+            let taker = Address::generate(&env);
+            let resolver = Address::generate(&env);
+            let admin = Address::generate(&env);
+            let token = env.register_stellar_asset_contract_v2(admin.clone()).address();
+            let native = env.register_stellar_asset_contract_v2(admin.clone()).address();
+            StellarAssetClient::new(&env, &native).mint(&resolver, &SAFETY_DEPOSIT);
+
+            let factory = UniteEscrowFactoryClient::new(&env, &env.register_contract(None, UniteEscrowFactory));
+            factory.initialize_factory(&admin, &BytesN::from_array(&env, &[0u8; 32]), &native);
+            factory.add_resolver(&resolver);
+
+            let timelocks = Timelocks::encode(offsets, 0);
+            let secret_hash = env.crypto().sha256(&Bytes::from_array(&env, &SECRET)).to_array();
+            let immutables = Immutables {
+                order_hash: BytesN::from_array(&env, &[1u8; 32]),
+                hashlock: BytesN::from_array(&env, &secret_hash),
+                maker: maker.clone(),
+                taker,
+                token: token.clone(),
+                amount: AMOUNT,
+                safety_deposit: SAFETY_DEPOSIT,
+                timelocks: U256::from_be_bytes(&env, &Bytes::from_array(&env, &timelocks.to_be_bytes())),
+            };
+
+            let escrow = UniteEscrowClient::new(&env, &env.register_contract(None, UniteEscrow));
+            let mut setup = Setup {
+                token: token::Client::new(&env, &token),
+                native: token::Client::new(&env, &native),
+                factory,
+                env,
+                escrow,
+                immutables,
+                maker,
+                resolver,
+                is_source,
+            };
+            setup.escrow = setup.new_escrow();
+            setup.escrow.add_resolver_safety_deposit(&setup.resolver, &AMOUNT);
+            StellarAssetClient::new(&setup.env, &token).mint(&setup.escrow.address, &AMOUNT);
+            setup
+        }
+
+        // Another escrow for the same order, initialized by the factory but with nobody joined
+        fn new_escrow(&self) -> UniteEscrowClient<'a> {
+            let escrow = UniteEscrowClient::new(&self.env, &self.env.register_contract(None, UniteEscrow));
+            if self.is_source {
+                escrow.initialize(&self.immutables, &true, &self.native.address, &self.factory.address);
+            } else {
+                escrow.initialize_dst(&self.immutables, &(DEPLOYED_AT + 150), &self.native.address, &self.factory.address);
+            }
+            escrow
+        }
+
+        // An allow-listed resolver holding a safety deposit
+        fn new_resolver(&self) -> Address {
+            let resolver = Address::generate(&self.env);
+            StellarAssetClient::new(&self.env, &self.native.address).mint(&resolver, &SAFETY_DEPOSIT);
+            self.factory.add_resolver(&resolver);
+            resolver
+        }
+
+        fn at(&self, offset: u64) {
+            self.env.ledger().set_timestamp(DEPLOYED_AT + offset);
+        }
+
+        fn secret(&self) -> BytesN<32> {
+            BytesN::from_array(&self.env, &SECRET)
+        }
+    }
+
+    #[test]
+    fn test_entry_points_require_caller_auth() {
+        let setup = Setup::new(true);
+        setup.at(50);
+        setup.escrow.withdraw_with_secret(&setup.resolver, &setup.secret(), &setup.immutables);
+        assert_eq!(
+            setup.env.auths(),
+            std::vec![(
+                setup.resolver.clone(),
+                AuthorizedInvocation {
+                    function: AuthorizedFunction::Contract((
+                        setup.escrow.address.clone(),
+                        Symbol::new(&setup.env, "withdraw_with_secret"),
+                        (setup.resolver.clone(), setup.secret(), setup.immutables.clone()).into_val(&setup.env),
+                    )),
+                    sub_invocations: std::vec![],
+                }
+            )]
+        );
+
+        // Without a signature nobody can act in someone else's name
+        let setup = Setup::new(true);
+        setup.env.set_auths(&[]);
+        assert!(setup.escrow.try_add_resolver_safety_deposit(&setup.maker, &AMOUNT).is_err());
+        setup.at(50);
+        assert!(setup
+            .escrow
+            .try_withdraw_with_secret(&setup.resolver, &setup.secret(), &setup.immutables)
+            .is_err());
+        setup.at(300);
+        assert!(setup.escrow.try_cancel(&setup.maker, &setup.immutables).is_err());
+    }
+
+    #[test]
+    fn test_private_withdrawal_is_for_takers() {
+        let setup = Setup::new(true);
+        let stranger = Address::generate(&setup.env);
+
+        setup.at(5);
+        assert_eq!(
+            setup.escrow.try_withdraw_with_secret(&setup.resolver, &setup.secret(), &setup.immutables),
+            Err(Ok(Error::InvalidTime.into()))
+        );
+
+        setup.at(50);
+        assert_eq!(
+            setup.escrow.try_withdraw_with_secret(&stranger, &setup.secret(), &setup.immutables),
+            Err(Ok(Error::InvalidCaller.into()))
+        );
+        assert_eq!(
+            setup.escrow.try_withdraw_with_secret(&setup.maker, &setup.secret(), &setup.immutables),
+            Err(Ok(Error::InvalidCaller.into()))
+        );
+
+        setup.escrow.withdraw_with_secret(&setup.resolver, &setup.secret(), &setup.immutables);
+        assert_eq!(setup.token.balance(&setup.resolver), AMOUNT);
+        assert_eq!(setup.native.balance(&setup.resolver), SAFETY_DEPOSIT);
+        assert_eq!(setup.native.balance(&setup.escrow.address), 0);
+    }
+
+    #[test]
+    fn test_public_withdrawal_is_open_until_cancellation() {
+        let setup = Setup::new(true);
+        let stranger = Address::generate(&setup.env);
+
+        setup.at(200);
+        assert_eq!(
+            setup.escrow.try_withdraw_with_secret(&stranger, &setup.secret(), &setup.immutables),
+            Err(Ok(Error::InvalidTime.into()))
+        );
+
+        setup.at(100);
+        setup.escrow.withdraw_with_secret(&stranger, &setup.secret(), &setup.immutables);
+        assert_eq!(setup.token.balance(&setup.resolver), AMOUNT);
+        // The public caller takes its share of the safety deposit
+        assert_eq!(setup.native.balance(&stranger), 10);
+        assert_eq!(setup.native.balance(&setup.resolver), 90);
+        assert_eq!(setup.native.balance(&setup.escrow.address), 0);
+    }
+
+    #[test]
+    fn test_safety_deposit_is_pulled_from_resolver() {
+        let setup = Setup::new(true);
+        assert_eq!(setup.native.balance(&setup.resolver), 0);
+        assert_eq!(setup.native.balance(&setup.escrow.address), SAFETY_DEPOSIT);
+        assert_eq!(setup.escrow.get_resolver_info(&setup.resolver), (AMOUNT, SAFETY_DEPOSIT, false));
+
+        // A resolver without the XLM to back its fill cannot join
+        let broke = Address::generate(&setup.env);
+        setup.factory.add_resolver(&broke);
+        assert!(setup.new_escrow().try_add_resolver_safety_deposit(&broke, &AMOUNT).is_err());
+    }
+
+    #[test]
+    fn test_only_allow_listed_resolvers_join() {
+        let setup = Setup::new(true);
+        let escrow = setup.new_escrow();
+        let stranger = Address::generate(&setup.env);
+        StellarAssetClient::new(&setup.env, &setup.native.address).mint(&stranger, &SAFETY_DEPOSIT);
+
+        assert_eq!(
+            escrow.try_add_resolver_safety_deposit(&stranger, &AMOUNT),
+            Err(Ok(Error::NotResolver.into()))
+        );
+        setup.at(50);
+        assert_eq!(
+            escrow.try_withdraw_with_secret(&stranger, &setup.secret(), &setup.immutables),
+            Err(Ok(Error::InvalidCaller.into()))
+        );
+
+        setup.factory.add_resolver(&stranger);
+        escrow.add_resolver_safety_deposit(&stranger, &AMOUNT);
+        assert_eq!(escrow.get_resolver_info(&stranger), (AMOUNT, SAFETY_DEPOSIT, false));
+    }
+
+    #[test]
+    fn test_fills_are_capped_at_the_escrowed_amount() {
+        let setup = Setup::new(true);
+        let escrow = setup.new_escrow();
+        let (first, second) = (setup.new_resolver(), setup.new_resolver());
+
+        escrow.add_resolver_safety_deposit(&first, &(AMOUNT - 100));
+        assert_eq!(
+            escrow.try_add_resolver_safety_deposit(&second, &101),
+            Err(Ok(Error::InvalidAmount.into()))
+        );
+        escrow.add_resolver_safety_deposit(&second, &100);
+
+        // The escrow is full
+        assert_eq!(
+            setup.escrow.try_add_resolver_safety_deposit(&first, &1),
+            Err(Ok(Error::InvalidAmount.into()))
+        );
+    }
+
+    #[test]
+    fn test_cannot_join_after_withdrawal_or_cancellation() {
+        let setup = Setup::new(true);
+        let late = setup.new_resolver();
+        setup.at(50);
+        setup.escrow.withdraw_with_secret(&setup.resolver, &setup.secret(), &setup.immutables);
+        assert_eq!(
+            setup.escrow.try_add_resolver_safety_deposit(&late, &1),
+            Err(Ok(Error::InvalidState.into()))
+        );
+
+        let setup = Setup::new(false);
+        let late = setup.new_resolver();
+        setup.at(200);
+        setup.escrow.cancel(&setup.resolver, &setup.immutables);
+        assert_eq!(
+            setup.escrow.try_add_resolver_safety_deposit(&late, &1),
+            Err(Ok(Error::InvalidState.into()))
+        );
+        assert_eq!(setup.native.balance(&late), SAFETY_DEPOSIT);
+    }
+
+    #[test]
+    fn test_destination_withdrawal_returns_safety_deposit() {
+        let setup = Setup::new(false);
+        setup.at(50);
+        setup.escrow.withdraw_with_secret(&setup.resolver, &setup.secret(), &setup.immutables);
+        assert_eq!(setup.token.balance(&setup.maker), AMOUNT);
+        assert_eq!(setup.native.balance(&setup.resolver), SAFETY_DEPOSIT);
+        assert_eq!(setup.native.balance(&setup.escrow.address), 0);
+    }
+
+    #[test]
+    fn test_source_cancellation_rules() {
+        let setup = Setup::new(true);
+        let stranger = Address::generate(&setup.env);
+
+        setup.at(199);
+        assert_eq!(setup.escrow.try_cancel(&setup.maker, &setup.immutables), Err(Ok(Error::InvalidTime.into())));
+
+        setup.at(200);
+        assert_eq!(setup.escrow.try_cancel(&stranger, &setup.immutables), Err(Ok(Error::InvalidCaller.into())));
+        setup.escrow.cancel(&setup.maker, &setup.immutables);
+        assert_eq!(setup.token.balance(&setup.maker), AMOUNT);
+        assert_eq!(setup.token.balance(&setup.resolver), 0);
+        assert_eq!(setup.native.balance(&setup.resolver), SAFETY_DEPOSIT);
+
+        // Anyone may cancel once public cancellation opens
+        let setup = Setup::new(true);
+        let stranger = Address::generate(&setup.env);
+        setup.at(300);
+        setup.escrow.cancel(&stranger, &setup.immutables);
+        assert_eq!(setup.token.balance(&setup.maker), AMOUNT);
+        assert_eq!(setup.native.balance(&stranger), 10);
+        assert_eq!(setup.native.balance(&setup.resolver), 90);
+        assert_eq!(setup.native.balance(&setup.escrow.address), 0);
+    }
+
+    #[test]
+    fn test_destination_cancellation_is_for_takers() {
+        let setup = Setup::new(false);
+
+        setup.at(200);
+        assert_eq!(setup.escrow.try_cancel(&setup.maker, &setup.immutables), Err(Ok(Error::InvalidCaller.into())));
+        setup.at(10_000);
+        assert_eq!(setup.escrow.try_cancel(&setup.maker, &setup.immutables), Err(Ok(Error::InvalidCaller.into())));
+
+        setup.escrow.cancel(&setup.resolver, &setup.immutables);
+        assert!(matches!(setup.escrow.get_state(), State::Cancelled));
+        assert_eq!(setup.token.balance(&setup.resolver), AMOUNT);
+        assert_eq!(setup.token.balance(&setup.maker), 0);
+        assert_eq!(setup.native.balance(&setup.resolver), SAFETY_DEPOSIT);
+
+        // Each resolver gets back the tokens it put in for its fill
+        setup.at(0);
+        let escrow = setup.new_escrow();
+        let second = setup.new_resolver();
+        escrow.add_resolver_safety_deposit(&setup.resolver, &600);
+        escrow.add_resolver_safety_deposit(&second, &400);
+        setup.token.transfer(&setup.resolver, &escrow.address, &600);
+        StellarAssetClient::new(&setup.env, &setup.token.address).mint(&escrow.address, &400);
+        setup.at(10_000);
+        escrow.cancel(&second, &setup.immutables);
+        assert_eq!(setup.token.balance(&setup.resolver), AMOUNT);
+        assert_eq!(setup.token.balance(&second), 400);
+        assert_eq!(setup.token.balance(&setup.maker), 0);
+        assert_eq!(setup.token.balance(&escrow.address), 0);
+        assert_eq!(setup.native.balance(&setup.resolver), SAFETY_DEPOSIT);
+        assert_eq!(setup.native.balance(&second), SAFETY_DEPOSIT);
+    }
+
+    #[test]
+    fn test_storage_outlives_cancellation() {
+        const DAY: u32 = 86_400;
+        let setup = Setup::with_timelocks(true, [10, 100, 60 * DAY, 90 * DAY, 10, 100, 60 * DAY]);
+        let instance_ttl = || setup.env.as_contract(&setup.escrow.address, || setup.env.storage().instance().get_ttl());
+        let resolver_ttl = || {
+            setup.env.as_contract(&setup.escrow.address, || {
+                setup.env.storage().persistent().get_ttl(&DataKey::Resolver(setup.resolver.clone()))
+            })
+        };
+
+        // Alive through public cancellation, 90 days of ledgers away, with a week to spare
+        let public_cancellation = 90 * DAY / LEDGER_CLOSE_SECONDS as u32;
+        assert!(instance_ttl() >= public_cancellation + INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS);
+        assert!(resolver_ttl() >= public_cancellation + INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS);
+    }
+
+    #[test]
+    fn test_anyone_can_extend() {
+        let setup = Setup::new(true);
+        let instance_ttl = || setup.env.as_contract(&setup.escrow.address, || setup.env.storage().instance().get_ttl());
+        let initial = instance_ttl();
+
+        setup.env.ledger().with_mut(|ledger| ledger.sequence_number += 2 * DAY_IN_LEDGERS);
+        assert_eq!(instance_ttl(), initial - 2 * DAY_IN_LEDGERS);
 
-        (amount, deposit, funds_distributed)
+        setup.env.set_auths(&[]);
+        setup.escrow.extend();
+        assert_eq!(instance_ttl(), initial);
     }
-}
\ No newline at end of file
+}
@@ -1,51 +1,160 @@
 use soroban_sdk::{
-    contract, contractimpl, token, Address, BytesN, Env, Map, Symbol,
-    symbol_short
+    contract, contractimpl, contracttype, token, xdr::ToXdr, Address, BytesN, Env,
+    IntoVal, Val, symbol_short, panic_with_error
 };
 
+use unite_timelocks::Stage;
+
+use crate::escrow::UniteEscrowClient;
+use crate::types::{
+    ttl_until, unpack_timelocks, Error, Immutables, DAY_IN_LEDGERS, INSTANCE_BUMP_AMOUNT,
+    INSTANCE_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT,
+};
+
+// Configuration sits in instance storage, every registry entry under its own persistent key
+#[contracttype(export = false)]
+#[derive(Clone)]
+enum DataKey {
+    Admin,
+    EscrowWasm,
+    NativeToken,
+    Resolver(Address),
+    Escrow(BytesN<32>),
+    FilledAmount(BytesN<32>),
+    // Last timelock stage of the escrow the factory deployed for an order
+    Deadline(BytesN<32>),
+}
+
 #[contract]
 pub struct UniteEscrowFactory;
 
 #[contractimpl]
 impl UniteEscrowFactory {
-    const ESCROWS: Symbol = symbol_short!("escrows");
-    const FILLED_AMOUNTS: Symbol = symbol_short!("filled");
+    /// `escrow_wasm_hash` is the uploaded `UniteEscrow` code, `native_token` the XLM
+    /// Stellar Asset Contract holding safety deposits. Named apart from `UniteEscrow::initialize`,
+    /// as every contract of this crate is exported from the same wasm.
+    pub fn initialize_factory(env: Env, admin: Address, escrow_wasm_hash: BytesN<32>, native_token: Address) {
+        if env.storage().instance().has(&DataKey::EscrowWasm) {
+            panic_with_error!(&env, Error::AlreadyInitialized);
+        }
+        admin.require_auth();
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::EscrowWasm, &escrow_wasm_hash);
+        env.storage().instance().set(&DataKey::NativeToken, &native_token);
+        env.storage().instance().extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+
+    /// Hands the factory over to `new_admin`, who must accept by signing too
+    pub fn transfer_admin(env: Env, new_admin: Address) {
+        let admin = Self::require_admin(&env);
+        new_admin.require_auth();
+
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        env.events().publish((symbol_short!("admin"), admin), new_admin);
+        env.storage().instance().extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+
+    pub fn add_resolver(env: Env, resolver: Address) {
+        Self::require_admin(&env);
+
+        Self::set_persistent(&env, &DataKey::Resolver(resolver.clone()), &true, PERSISTENT_BUMP_AMOUNT);
+        env.events().publish((symbol_short!("res_add"), resolver), ());
+        env.storage().instance().extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+
+    pub fn remove_resolver(env: Env, resolver: Address) {
+        Self::require_admin(&env);
+
+        env.storage().persistent().remove(&DataKey::Resolver(resolver.clone()));
+        env.events().publish((symbol_short!("res_rm"), resolver), ());
+        env.storage().instance().extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+
+    pub fn get_admin(env: Env) -> Address {
+        env.storage().instance()
+            .get(&DataKey::Admin)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotInitialized))
+    }
+
+    pub fn is_resolver(env: Env, resolver: Address) -> bool {
+        env.storage().persistent().has(&DataKey::Resolver(resolver))
+    }
+
+    /// Bumps the factory instance and, when given, the registry entries of `order_hash`.
+    /// Anyone may call it.
+    pub fn extend_order(env: Env, order_hash: Option<BytesN<32>>) {
+        env.storage().instance().extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        if let Some(order_hash) = order_hash {
+            let ttl = Self::order_ttl(&env, &order_hash);
+            let keys = [
+                DataKey::Escrow(order_hash.clone()),
+                DataKey::FilledAmount(order_hash.clone()),
+                DataKey::Deadline(order_hash),
+            ];
+            for key in keys {
+                if env.storage().persistent().has(&key) {
+                    env.storage().persistent().extend_ttl(&key, ttl.saturating_sub(DAY_IN_LEDGERS), ttl);
+                }
+            }
+        }
+    }
+
+    /// Deploys and initializes the source escrow of an order, returning its address
+    pub fn create_src_escrow(env: Env, resolver: Address, immutables: Immutables) -> Address {
+        Self::require_resolver(&env, &resolver);
+        let (escrow, native_token) = Self::deploy_escrow(&env, &immutables, true);
+        let factory = env.current_contract_address();
+        UniteEscrowClient::new(&env, &escrow).initialize(&immutables, &true, &native_token, &factory);
+        escrow
+    }
 
-    // In Stellar, we can't deploy contracts dynamically like in EVM
-    // Instead, we'll store escrow addresses that are pre-deployed
+    /// Deploys and initializes the destination escrow of an order, returning its address
+    pub fn create_dst_escrow(
+        env: Env,
+        resolver: Address,
+        immutables: Immutables,
+        src_cancellation_timestamp: u64
+    ) -> Address {
+        Self::require_resolver(&env, &resolver);
+        let (escrow, native_token) = Self::deploy_escrow(&env, &immutables, false);
+        let factory = env.current_contract_address();
+        UniteEscrowClient::new(&env, &escrow).initialize_dst(&immutables, &src_cancellation_timestamp, &native_token, &factory);
+        escrow
+    }
+
+    /// Address `create_src_escrow` / `create_dst_escrow` deploy to for `immutables`
+    pub fn predict_address(env: Env, immutables: Immutables, is_source: bool) -> Address {
+        let salt = Self::escrow_salt(&env, &immutables, is_source);
+        env.deployer().with_current_contract(salt).deployed_address()
+    }
+
+    /// Points `order_hash` at an escrow deployed outside the factory, unless it already has one
     pub fn register_escrow(env: Env, order_hash: BytesN<32>, escrow_address: Address) {
-        let mut escrows: Map<BytesN<32>, Address> = env.storage().instance()
-            .get(&Self::ESCROWS)
-            .unwrap_or(Map::new(&env));
-        
-        escrows.set(order_hash, escrow_address);
-        env.storage().instance().set(&Self::ESCROWS, &escrows);
-        env.storage().instance().extend_ttl(100, 10000);
+        Self::require_admin(&env);
+        Self::set_escrow(&env, order_hash, escrow_address);
     }
 
-    pub fn update_filled_amount(env: Env, order_hash: BytesN<32>, additional_amount: i128) {
-        let mut filled_amounts: Map<BytesN<32>, i128> = env.storage().instance()
-            .get(&Self::FILLED_AMOUNTS)
-            .unwrap_or(Map::new(&env));
-        
-        let current_filled = filled_amounts.get(order_hash.clone()).unwrap_or(0);
-        filled_amounts.set(order_hash, current_filled + additional_amount);
-        env.storage().instance().set(&Self::FILLED_AMOUNTS, &filled_amounts);
-        env.storage().instance().extend_ttl(100, 10000);
+    pub fn update_filled_amount(env: Env, resolver: Address, order_hash: BytesN<32>, additional_amount: i128) {
+        Self::require_resolver(&env, &resolver);
+        if additional_amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+
+        let key = DataKey::FilledAmount(order_hash.clone());
+        let current_filled: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        let ttl = Self::order_ttl(&env, &order_hash);
+        Self::set_persistent(&env, &key, &(current_filled + additional_amount), ttl);
+        env.events().publish((symbol_short!("filled"), order_hash), (resolver, additional_amount));
+        env.storage().instance().extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
     }
 
     pub fn get_escrow_address(env: Env, order_hash: BytesN<32>) -> Option<Address> {
-        let escrows: Map<BytesN<32>, Address> = env.storage().instance()
-            .get(&Self::ESCROWS)
-            .unwrap_or(Map::new(&env));
-        escrows.get(order_hash)
+        env.storage().persistent().get(&DataKey::Escrow(order_hash))
     }
 
     pub fn get_total_filled_amount(env: Env, order_hash: BytesN<32>) -> i128 {
-        let filled_amounts: Map<BytesN<32>, i128> = env.storage().instance()
-            .get(&Self::FILLED_AMOUNTS)
-            .unwrap_or(Map::new(&env));
-        filled_amounts.get(order_hash).unwrap_or(0)
+        env.storage().persistent().get(&DataKey::FilledAmount(order_hash)).unwrap_or(0)
     }
 
     pub fn transfer_user_funds(
@@ -57,12 +166,348 @@ impl UniteEscrowFactory {
     ) {
         from.require_auth();
         
-        let escrows: Map<BytesN<32>, Address> = env.storage().instance()
-            .get(&Self::ESCROWS)
-            .unwrap();
-        let escrow_address = escrows.get(order_hash).unwrap();
+        let escrow_address: Address = env.storage().persistent().get(&DataKey::Escrow(order_hash)).unwrap();
         
         let token_client = token::Client::new(&env, &token);
         token_client.transfer_from(&env.current_contract_address(), &from, &escrow_address, &amount);
     }
-}
\ No newline at end of file
+
+    fn require_admin(env: &Env) -> Address {
+        let admin = Self::get_admin(env.clone());
+        admin.require_auth();
+        admin
+    }
+
+    fn require_resolver(env: &Env, resolver: &Address) {
+        resolver.require_auth();
+        if !env.storage().instance().has(&DataKey::Admin) {
+            panic_with_error!(env, Error::NotInitialized);
+        }
+        if !env.storage().persistent().has(&DataKey::Resolver(resolver.clone())) {
+            panic_with_error!(env, Error::NotResolver);
+        }
+    }
+
+    fn deploy_escrow(env: &Env, immutables: &Immutables, is_source: bool) -> (Address, Address) {
+        let wasm_hash: BytesN<32> = env.storage().instance()
+            .get(&DataKey::EscrowWasm)
+            .unwrap_or_else(|| panic_with_error!(env, Error::NotInitialized));
+        let native_token: Address = env.storage().instance().get(&DataKey::NativeToken).unwrap();
+
+        // The escrow counts its stages from now, and its registry entries must outlive the last one
+        let deployed_at = u32::try_from(env.ledger().timestamp())
+            .unwrap_or_else(|_| panic_with_error!(env, Error::InvalidTime));
+        let last_stage = if is_source { Stage::SrcPublicCancellation } else { Stage::DstCancellation };
+        let deadline = unpack_timelocks(&immutables.timelocks).with_deployed_at(deployed_at).get(last_stage);
+        let deadline_key = DataKey::Deadline(immutables.order_hash.clone());
+        Self::set_persistent(env, &deadline_key, &deadline, ttl_until(env, deadline));
+
+        let salt = Self::escrow_salt(env, immutables, is_source);
+        let escrow = env.deployer().with_current_contract(salt).deploy(wasm_hash);
+        Self::set_escrow(env, immutables.order_hash.clone(), escrow.clone());
+        (escrow, native_token)
+    }
+
+    // An order keeps the first escrow registered for it, so lookups cannot be redirected
+    fn set_escrow(env: &Env, order_hash: BytesN<32>, escrow_address: Address) {
+        if env.storage().persistent().has(&DataKey::Escrow(order_hash.clone())) {
+            panic_with_error!(env, Error::EscrowExists);
+        }
+        let ttl = Self::order_ttl(env, &order_hash);
+        Self::set_persistent(env, &DataKey::Escrow(order_hash.clone()), &escrow_address, ttl);
+        env.events().publish((symbol_short!("escrow"), order_hash), escrow_address);
+        env.storage().instance().extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    }
+
+    // Entries of an order live until a week past its escrow's last stage, like the escrow itself,
+    // or a month past their last write when the factory did not deploy the escrow
+    fn order_ttl(env: &Env, order_hash: &BytesN<32>) -> u32 {
+        match env.storage().persistent().get(&DataKey::Deadline(order_hash.clone())) {
+            Some(deadline) => ttl_until(env, deadline),
+            None => PERSISTENT_BUMP_AMOUNT,
+        }
+    }
+
+    fn set_persistent<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V, ttl: u32) {
+        env.storage().persistent().set(key, value);
+        env.storage().persistent().extend_ttl(key, ttl.saturating_sub(DAY_IN_LEDGERS), ttl);
+    }
+
+    // One escrow per side of an order: keccak256(xdr(immutables) || is_source)
+    fn escrow_salt(env: &Env, immutables: &Immutables, is_source: bool) -> BytesN<32> {
+        let mut data = immutables.clone().to_xdr(env);
+        data.push_back(is_source as u8);
+        env.crypto().keccak256(&data).into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::testutils::storage::{Instance as _, Persistent as _};
+    use soroban_sdk::testutils::{Address as _, Events, Ledger, MockAuth, MockAuthInvoke};
+    use crate::types::{State, DAY_IN_LEDGERS, LEDGER_CLOSE_SECONDS};
+    use soroban_sdk::{vec, testutils::EnvTestConfig, Bytes, IntoVal, U256};
+
+    fn immutables(env: &Env, amount: i128) -> Immutables {
+        Immutables {
+            order_hash: BytesN::from_array(env, &[1u8; 32]),
+            hashlock: BytesN::from_array(env, &[2u8; 32]),
+            maker: Address::generate(env),
+            taker: Address::generate(env),
+            token: Address::generate(env),
+            amount,
+            safety_deposit: 100,
+            timelocks: U256::from_u32(env, 0),
+        }
+    }
+
+    fn factory(env: &Env) -> UniteEscrowFactoryClient<'_> {
+        env.mock_all_auths();
+        let factory = UniteEscrowFactoryClient::new(env, &env.register_contract(None, UniteEscrowFactory));
+        factory.initialize_factory(&Address::generate(env), &BytesN::from_array(env, &[0u8; 32]), &Address::generate(env));
+        factory
+    }
+
+    #[test]
+    fn test_predict_address_is_deterministic_per_order_and_side() {
+        let env = Env::default();
+        let factory = factory(&env);
+        let immutables = immutables(&env, 1_000);
+
+        let src = factory.predict_address(&immutables, &true);
+        assert_eq!(factory.predict_address(&immutables, &true), src);
+        assert_ne!(factory.predict_address(&immutables, &false), src);
+
+        let mut other = immutables.clone();
+        other.amount = 999;
+        assert_ne!(factory.predict_address(&other, &true), src);
+    }
+
+    #[test]
+    fn test_initialize_once() {
+        let env = Env::default();
+        let factory = factory(&env);
+        assert_eq!(
+            factory.try_initialize_factory(&Address::generate(&env), &BytesN::from_array(&env, &[0u8; 32]), &Address::generate(&env)),
+            Err(Ok(Error::AlreadyInitialized.into()))
+        );
+
+        let uninitialized = UniteEscrowFactoryClient::new(&env, &env.register_contract(None, UniteEscrowFactory));
+        assert_eq!(
+            uninitialized.try_update_filled_amount(&Address::generate(&env), &BytesN::from_array(&env, &[1u8; 32]), &1),
+            Err(Ok(Error::NotInitialized.into()))
+        );
+    }
+
+    #[test]
+    fn test_only_allowed_resolvers_record_fills() {
+        let env = Env::default();
+        let factory = factory(&env);
+        let resolver = Address::generate(&env);
+        let order_hash = BytesN::from_array(&env, &[1u8; 32]);
+
+        assert_eq!(
+            factory.try_update_filled_amount(&resolver, &order_hash, &100),
+            Err(Ok(Error::NotResolver.into()))
+        );
+        assert_eq!(
+            factory.try_create_src_escrow(&resolver, &immutables(&env, 1_000)),
+            Err(Ok(Error::NotResolver.into()))
+        );
+
+        factory.add_resolver(&resolver);
+        assert!(factory.is_resolver(&resolver));
+        factory.update_filled_amount(&resolver, &order_hash, &100);
+        assert_eq!(factory.get_total_filled_amount(&order_hash), 100);
+        assert_eq!(
+            vec![&env, env.events().all().last().unwrap()],
+            vec![
+                &env,
+                (
+                    factory.address.clone(),
+                    (symbol_short!("filled"), order_hash.clone()).into_val(&env),
+                    (resolver.clone(), 100i128).into_val(&env),
+                )
+            ]
+        );
+
+        factory.remove_resolver(&resolver);
+        assert_eq!(
+            factory.try_update_filled_amount(&resolver, &order_hash, &100),
+            Err(Ok(Error::NotResolver.into()))
+        );
+        assert_eq!(factory.get_total_filled_amount(&order_hash), 100);
+    }
+
+    #[test]
+    fn test_registry_mutations_need_signatures() {
+        let env = Env::default();
+        let factory = factory(&env);
+        let admin = factory.get_admin();
+        let resolver = Address::generate(&env);
+        let order_hash = BytesN::from_array(&env, &[1u8; 32]);
+        factory.add_resolver(&resolver);
+
+        env.set_auths(&[]);
+        assert!(factory.try_add_resolver(&Address::generate(&env)).is_err());
+        assert!(factory.try_remove_resolver(&resolver).is_err());
+        assert!(factory.try_register_escrow(&order_hash, &Address::generate(&env)).is_err());
+        // An allowed resolver still has to sign for itself
+        assert!(factory.try_update_filled_amount(&resolver, &order_hash, &100).is_err());
+
+        // Only the admin's signature registers an escrow
+        let escrow = Address::generate(&env);
+        env.mock_auths(&[MockAuth {
+            address: &admin,
+            invoke: &MockAuthInvoke {
+                contract: &factory.address,
+                fn_name: "register_escrow",
+                args: (order_hash.clone(), escrow.clone()).into_val(&env),
+                sub_invokes: &[],
+            },
+        }]);
+        factory.register_escrow(&order_hash, &escrow);
+        assert_eq!(factory.get_escrow_address(&order_hash), Some(escrow.clone()));
+
+        // Not even the admin can point the order at another escrow
+        env.mock_all_auths();
+        assert_eq!(
+            factory.try_register_escrow(&order_hash, &Address::generate(&env)),
+            Err(Ok(Error::EscrowExists.into()))
+        );
+        assert_eq!(factory.get_escrow_address(&order_hash), Some(escrow));
+    }
+
+    #[test]
+    fn test_create_escrow_deploys_to_predicted_address() {
+        let env = Env::default();
+        env.mock_all_auths();
+        // The contract wasm, built by build.rs
+        let wasm = include_bytes!(env!("UNITE_STELLAR_WASM"));
+        let wasm_hash = env.deployer().upload_contract_wasm(Bytes::from_slice(&env, wasm));
+        let native = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+        let factory = UniteEscrowFactoryClient::new(&env, &env.register_contract(None, UniteEscrowFactory));
+        factory.initialize_factory(&Address::generate(&env), &wasm_hash, &native);
+        let resolver = Address::generate(&env);
+        factory.add_resolver(&resolver);
+
+        let immutables = immutables(&env, 1_000);
+        let predicted = factory.predict_address(&immutables, &true);
+        let escrow = factory.create_src_escrow(&resolver, &immutables);
+        assert_eq!(escrow, predicted);
+        assert_eq!(factory.get_escrow_address(&immutables.order_hash), Some(escrow.clone()));
+
+        let escrow = UniteEscrowClient::new(&env, &escrow);
+        assert_eq!(escrow.get_order_hash(), immutables.order_hash);
+        assert!(matches!(escrow.get_state(), State::Active));
+        assert_eq!(
+            escrow.try_initialize(&immutables, &true, &native, &factory.address),
+            Err(Ok(Error::AlreadyInitialized.into()))
+        );
+
+        // The order already has its escrow
+        assert_eq!(
+            factory.try_create_dst_escrow(&resolver, &immutables, &0),
+            Err(Ok(Error::EscrowExists.into()))
+        );
+    }
+
+    #[test]
+    fn test_transfer_admin() {
+        let env = Env::default();
+        let factory = factory(&env);
+        let admin = factory.get_admin();
+        let new_admin = Address::generate(&env);
+
+        factory.transfer_admin(&new_admin);
+        assert_eq!(factory.get_admin(), new_admin);
+        assert_eq!(
+            env.events().all(),
+            vec![
+                &env,
+                (
+                    factory.address.clone(),
+                    (symbol_short!("admin"), admin).into_val(&env),
+                    new_admin.into_val(&env),
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_anyone_can_extend_an_order() {
+        let env = Env::default();
+        let factory = factory(&env);
+        let order_hash = BytesN::from_array(&env, &[1u8; 32]);
+        factory.register_escrow(&order_hash, &Address::generate(&env));
+        let escrow_ttl = || {
+            env.as_contract(&factory.address, || env.storage().persistent().get_ttl(&DataKey::Escrow(order_hash.clone())))
+        };
+
+        env.ledger().with_mut(|ledger| ledger.sequence_number += 2 * DAY_IN_LEDGERS);
+        assert_eq!(escrow_ttl(), PERSISTENT_BUMP_AMOUNT - 2 * DAY_IN_LEDGERS);
+
+        env.set_auths(&[]);
+        factory.extend_order(&Some(order_hash.clone()));
+        assert_eq!(escrow_ttl(), PERSISTENT_BUMP_AMOUNT);
+        factory.extend_order(&None);
+    }
+
+    #[test]
+    fn test_order_entries_live_until_the_escrow_deadline() {
+        let env = Env::default();
+        let factory = factory(&env);
+        let resolver = Address::generate(&env);
+        factory.add_resolver(&resolver);
+        let order_hash = BytesN::from_array(&env, &[1u8; 32]);
+
+        // As `create_src_escrow` records it for an escrow whose last stage is 90 days away
+        let deadline = env.ledger().timestamp() + 90 * 86_400;
+        env.as_contract(&factory.address, || {
+            let key = DataKey::Deadline(order_hash.clone());
+            UniteEscrowFactory::set_persistent(&env, &key, &deadline, ttl_until(&env, deadline))
+        });
+        factory.register_escrow(&order_hash, &Address::generate(&env));
+        factory.update_filled_amount(&resolver, &order_hash, &100);
+
+        let ttl = |key: DataKey| env.as_contract(&factory.address, || env.storage().persistent().get_ttl(&key));
+        let expected = 90 * 86_400 / LEDGER_CLOSE_SECONDS as u32 + INSTANCE_BUMP_AMOUNT;
+        assert!(expected > PERSISTENT_BUMP_AMOUNT);
+        assert_eq!(ttl(DataKey::Escrow(order_hash.clone())), expected);
+        assert_eq!(ttl(DataKey::FilledAmount(order_hash.clone())), expected);
+
+        env.ledger().with_mut(|ledger| ledger.sequence_number += 2 * DAY_IN_LEDGERS);
+        factory.extend_order(&Some(order_hash.clone()));
+        assert_eq!(ttl(DataKey::Escrow(order_hash.clone())), expected);
+        assert_eq!(ttl(DataKey::FilledAmount(order_hash.clone())), expected);
+        assert_eq!(ttl(DataKey::Deadline(order_hash)), expected);
+    }
+
+    #[test]
+    fn test_registry_entries_do_not_grow_the_instance() {
+        let env = Env::new_with_config(EnvTestConfig { capture_snapshot_at_drop: false });
+        let factory = factory(&env);
+        let resolver = Address::generate(&env);
+        factory.add_resolver(&resolver);
+
+        let order_hash = |index: u32| {
+            let mut order_hash = [0u8; 32];
+            order_hash[..4].copy_from_slice(&index.to_be_bytes());
+            BytesN::from_array(&env, &order_hash)
+        };
+        let instance_entries = || env.as_contract(&factory.address, || env.storage().instance().all().len());
+
+        // The test host meters the whole test as one transaction
+        env.budget().reset_unlimited();
+        let before = instance_entries();
+        for index in 0..2_000 {
+            factory.update_filled_amount(&resolver, &order_hash(index), &1);
+        }
+
+        // Every order lives under its own persistent key, the instance loaded on each call is unchanged
+        assert_eq!(instance_entries(), before);
+        assert_eq!(env.as_contract(&factory.address, || env.storage().persistent().all().len()), 2_001);
+        assert_eq!(factory.get_total_filled_amount(&order_hash(1_999)), 1);
+    }
+}
@@ -1,6 +1,7 @@
 #![no_std]
 
 mod types;
+mod eip712;
 mod escrow;
 mod resolver;
 mod token;
@@ -3,28 +3,30 @@ use soroban_sdk::{
     log, panic_with_error
 };
 
-use crate::types::{Error, Immutables, Order, EVM_DECIMAL_FACTOR, DECIMAL_FACTOR};
+use crate::eip712;
+use crate::types::{
+    AuctionDetails, Error, Immutables, Order, EVM_DECIMAL_FACTOR, DECIMAL_FACTOR, MAX_AUCTION_POINTS
+};
+use unite_auction::{mul_div, Auction, Curve, Point};
 
 #[contract]
 pub struct UniteResolver;
 
 #[contractimpl]
 impl UniteResolver {
-    pub fn calculate_current_price(env: Env, start_price: u128, end_price: u128, start_time: u64, end_time: u64) -> u128 {
-        let current_time = env.ledger().timestamp();
-        
-        if current_time <= start_time {
-            return start_price;
-        }
-        if current_time >= end_time {
-            return end_price;
-        }
-
-        let elapsed = current_time - start_time;
-        let duration = end_time - start_time;
-        let price_diff = start_price - end_price;
-
-        start_price - (price_diff * elapsed as u128) / duration as u128
+    pub fn calculate_current_price(
+        env: Env,
+        start_price: u128,
+        end_price: u128,
+        start_time: u64,
+        end_time: u64,
+        auction_details: AuctionDetails
+    ) -> u128 {
+        let mut points = [NO_POINT; MAX_AUCTION_POINTS];
+        let auction = auction(&env, start_price, end_price, start_time, end_time, &auction_details, &mut points);
+        auction
+            .price(env.ledger().timestamp())
+            .unwrap_or_else(|error| panic_with_error!(&env, auction_error(error)))
     }
 
     pub fn calculate_taking_amount(
@@ -33,10 +35,14 @@ impl UniteResolver {
         start_price: u128,
         end_price: u128,
         start_time: u64,
-        end_time: u64
+        end_time: u64,
+        auction_details: AuctionDetails
     ) -> u128 {
-        let current_price = Self::calculate_current_price(env.clone(), start_price, end_price, start_time, end_time);
-        (making_amount * current_price) / EVM_DECIMAL_FACTOR as u128
+        let mut points = [NO_POINT; MAX_AUCTION_POINTS];
+        let auction = auction(&env, start_price, end_price, start_time, end_time, &auction_details, &mut points);
+        auction
+            .taking_amount(making_amount, env.ledger().timestamp())
+            .unwrap_or_else(|error| panic_with_error!(&env, auction_error(error)))
     }
 
     pub fn fill_order(
@@ -57,51 +63,186 @@ impl UniteResolver {
             order.start_price,
             order.end_price,
             order.auction_start_time,
-            order.auction_end_time
+            order.auction_end_time,
+            order.auction_details
         );
 
-        let stellar_dest_amount = (dest_amount as i128 * DECIMAL_FACTOR) / EVM_DECIMAL_FACTOR;
+        let stellar_dest_amount = mul_div(dest_amount, DECIMAL_FACTOR as u128, EVM_DECIMAL_FACTOR as u128)
+            .and_then(|amount| i128::try_from(amount).ok())
+            .unwrap_or_else(|| panic_with_error!(&env, Error::InvalidAmount));
 
         log!(&env, "Fill order: src_amount={}, dest_amount={}, stellar_amount={}", 
              src_amount, dest_amount, stellar_dest_amount);
 
         let token_client = token::Client::new(&env, &immutables.token);
-        // The invoker should have pre-authorized this transfer
-        token_client.transfer(&escrow_factory, &escrow_factory, &stellar_dest_amount);
+        // The factory pays the taker, and should have pre-authorized this transfer
+        token_client.transfer(&escrow_factory, &immutables.taker, &stellar_dest_amount);
     }
 
+    /// EVM-compatible order hash (`UniteLimitOrderProtocol.hashOrder`)
     pub fn hash_order(env: Env, order: Order) -> BytesN<32> {
-        // For cross-chain compatibility, we need to carefully construct the hash
-        // In production, this would need to match EVM's keccak256 exactly
-        
-        // Create a Bytes object to hold all data
-        let mut data = soroban_sdk::Bytes::new(&env);
-        
-        // Append all order fields as bytes
-        // Salt
-        data.append(&soroban_sdk::Bytes::from_array(&env, &order.salt.to_be_bytes()));
-        
-        // Addresses (already 32 bytes)
-        data.append(&soroban_sdk::Bytes::from_array(&env, &order.maker.to_array()));
-        data.append(&soroban_sdk::Bytes::from_array(&env, &order.receiver.to_array()));
-        data.append(&soroban_sdk::Bytes::from_array(&env, &order.maker_asset.to_array()));
-        data.append(&soroban_sdk::Bytes::from_array(&env, &order.taker_asset.to_array()));
-        
-        // Amounts
-        data.append(&soroban_sdk::Bytes::from_array(&env, &order.making_amount.to_be_bytes()));
-        data.append(&soroban_sdk::Bytes::from_array(&env, &order.taking_amount.to_be_bytes()));
-        
-        // Timestamps and other fields
-        data.append(&soroban_sdk::Bytes::from_array(&env, &order.deadline.to_be_bytes()));
-        data.append(&soroban_sdk::Bytes::from_array(&env, &order.nonce.to_be_bytes()));
-        data.append(&soroban_sdk::Bytes::from_array(&env, &order.src_chain_id.to_be_bytes()));
-        data.append(&soroban_sdk::Bytes::from_array(&env, &order.dst_chain_id.to_be_bytes()));
-        data.append(&soroban_sdk::Bytes::from_array(&env, &order.auction_start_time.to_be_bytes()));
-        data.append(&soroban_sdk::Bytes::from_array(&env, &order.auction_end_time.to_be_bytes()));
-        data.append(&soroban_sdk::Bytes::from_array(&env, &order.start_price.to_be_bytes()));
-        data.append(&soroban_sdk::Bytes::from_array(&env, &order.end_price.to_be_bytes()));
-
-        let hash = env.crypto().sha256(&data);
-        BytesN::from_array(&env, &hash.to_array())
+        eip712::hash_order(&env, &order)
+    }
+
+    /// EIP-712 digest the maker signs for `order` on the EVM protocol at `verifying_contract`
+    pub fn order_digest(env: Env, order: Order, chain_id: u128, verifying_contract: BytesN<32>) -> BytesN<32> {
+        let separator = eip712::domain_separator(&env, chain_id, &verifying_contract);
+        eip712::typed_data_hash(&env, &separator, &eip712::hash_order(&env, &order))
+    }
+}
+
+const NO_POINT: Point = Point { delay: 0, rate_bump: 0 };
+
+/// Auction over `details`, with piecewise-linear points copied into `points`
+fn auction<'a>(
+    env: &Env,
+    start_price: u128,
+    end_price: u128,
+    start_time: u64,
+    end_time: u64,
+    details: &AuctionDetails,
+    points: &'a mut [Point; MAX_AUCTION_POINTS]
+) -> Auction<'a> {
+    let curve = match details {
+        AuctionDetails::Linear => Curve::Linear,
+        AuctionDetails::PiecewiseLinear(initial_rate_bump, curve_points) => {
+            let len = curve_points.len() as usize;
+            if len > MAX_AUCTION_POINTS {
+                panic_with_error!(env, Error::InvalidPrice);
+            }
+            for (slot, point) in points.iter_mut().zip(curve_points.iter()) {
+                *slot = Point { delay: point.delay, rate_bump: point.rate_bump };
+            }
+            Curve::PiecewiseLinear { initial_rate_bump: *initial_rate_bump, points: &points[..len] }
+        }
+        AuctionDetails::Exponential(half_life) => Curve::Exponential { half_life: *half_life },
+    };
+    Auction { start_price, end_price, start_time, end_time, curve }
+}
+
+fn auction_error(error: unite_auction::Error) -> Error {
+    match error {
+        unite_auction::Error::NotStarted | unite_auction::Error::InvalidDuration => Error::InvalidTime,
+        unite_auction::Error::InvalidPrice => Error::InvalidPrice,
+        unite_auction::Error::Overflow => Error::InvalidAmount,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::AuctionPoint;
+    use soroban_sdk::testutils::{Address as _, Ledger};
+    use crate::token::{MockToken, MockTokenClient};
+    use soroban_sdk::{String, Vec, U256};
+    use unite_auction::vectors;
+
+    fn auction_details(env: &Env, curve: Curve) -> AuctionDetails {
+        match curve {
+            Curve::Linear => AuctionDetails::Linear,
+            Curve::PiecewiseLinear { initial_rate_bump, points } => {
+                let mut curve_points = Vec::new(env);
+                for point in points {
+                    curve_points.push_back(AuctionPoint { delay: point.delay, rate_bump: point.rate_bump });
+                }
+                AuctionDetails::PiecewiseLinear(initial_rate_bump, curve_points)
+            }
+            Curve::Exponential { half_life } => AuctionDetails::Exponential(half_life),
+        }
+    }
+
+    #[test]
+    fn shared_vectors() {
+        let env = Env::default();
+        let resolver = UniteResolverClient::new(&env, &env.register_contract(None, UniteResolver));
+
+        for vector in vectors::linear().chain(vectors::curves()) {
+            env.ledger().set_timestamp(vector.now);
+            let (start_price, end_price) = (&vector.start_price, &vector.end_price);
+            let (start_time, end_time) = (&vector.start_time, &vector.end_time);
+            let details = auction_details(&env, vector.curve());
+            let price = resolver.try_calculate_current_price(start_price, end_price, start_time, end_time, &details);
+            let taking_amount = resolver.try_calculate_taking_amount(
+                &vector.making_amount, start_price, end_price, start_time, end_time, &details
+            );
+            match vector.expected {
+                Ok((expected_price, expected_taking_amount)) => {
+                    assert_eq!(price, Ok(Ok(expected_price)), "{vector:?}");
+                    assert_eq!(taking_amount, Ok(Ok(expected_taking_amount)), "{vector:?}");
+                }
+                Err(error) => {
+                    assert_eq!(taking_amount, Err(Ok(auction_error(error).into())), "{vector:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fill_order_scales_amounts_past_i128() {
+        let env = Env::default();
+        // The factory authorizes the transfer inside `fill_order`, not at the root call
+        env.mock_all_auths_allowing_non_root_auth();
+        env.ledger().set_timestamp(1_000);
+        let resolver = UniteResolverClient::new(&env, &env.register_contract(None, UniteResolver));
+        let factory = Address::generate(&env);
+        let token = env.register_contract(None, MockToken);
+        let token_client = MockTokenClient::new(&env, &token);
+        token_client.init(&Address::generate(&env), &6, &String::from_str(&env, "USDT"), &String::from_str(&env, "USDT"));
+        let taker = Address::generate(&env);
+        let word = BytesN::from_array(&env, &[0u8; 32]);
+        let immutables = Immutables {
+            order_hash: word.clone(),
+            hashlock: word.clone(),
+            maker: Address::generate(&env),
+            taker: taker.clone(),
+            token: token.clone(),
+            amount: 1,
+            safety_deposit: 0,
+            timelocks: U256::from_u32(&env, 0),
+        };
+        let order = Order {
+            salt: 0,
+            maker: word.clone(),
+            receiver: word.clone(),
+            maker_asset: word.clone(),
+            taker_asset: word,
+            making_amount: u128::MAX,
+            taking_amount: u128::MAX,
+            deadline: 2_000,
+            nonce: 0,
+            src_chain_id: 1,
+            dst_chain_id: 2,
+            auction_start_time: 0,
+            auction_end_time: 1_000,
+            start_price: 1_000_000_000_000_000_000,
+            end_price: 1_000_000_000_000_000_000,
+            auction_details: AuctionDetails::Linear,
+        };
+
+        // A taking amount of u128::MAX is 340282366920938463463374607 in 6 decimals,
+        // so the factory has to hold exactly that much
+        let expected: i128 = 340_282_366_920_938_463_463_374_607;
+        token_client.mint(&factory, &(expected - 1));
+        let fill = || resolver.try_fill_order(&factory, &immutables, &order, &0, &u128::MAX);
+        assert!(fill().is_err());
+        token_client.mint(&factory, &1);
+        assert_eq!(fill(), Ok(Ok(())));
+        assert_eq!(token_client.balance(&factory), 0);
+        assert_eq!(token_client.balance(&taker), expected);
+    }
+
+    #[test]
+    fn rejects_curves_with_too_many_points() {
+        let env = Env::default();
+        let resolver = UniteResolverClient::new(&env, &env.register_contract(None, UniteResolver));
+        let mut points = Vec::new(&env);
+        for _ in 0..=MAX_AUCTION_POINTS {
+            points.push_back(AuctionPoint { delay: 1, rate_bump: 0 });
+        }
+        let details = AuctionDetails::PiecewiseLinear(0, points);
+        assert_eq!(
+            resolver.try_calculate_current_price(&0, &1, &0, &100, &details),
+            Err(Ok(Error::InvalidPrice.into()))
+        );
     }
-}
\ No newline at end of file
+}
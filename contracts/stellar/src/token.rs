@@ -2,7 +2,10 @@ use soroban_sdk::{
     contract, contractimpl, Address, Env, String, symbol_short, panic_with_error
 };
 
-use crate::types::Error;
+use crate::types::{
+    Error, INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT,
+    PERSISTENT_LIFETIME_THRESHOLD,
+};
 
 #[contract]
 pub struct MockToken;
@@ -15,7 +18,7 @@ impl MockToken {
         storage.set(&symbol_short!("decimal"), &decimal);
         storage.set(&symbol_short!("name"), &name);
         storage.set(&symbol_short!("symbol"), &symbol);
-        storage.extend_ttl(100, 10000);
+        storage.extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
     }
 
     pub fn mint(env: Env, to: Address, amount: i128) {
@@ -28,7 +31,7 @@ impl MockToken {
             .unwrap_or(0);
         
         env.storage().persistent().set(&balance_key, &(current + amount));
-        env.storage().persistent().extend_ttl(&balance_key, 100, 10000);
+        env.storage().persistent().extend_ttl(&balance_key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
     }
 
     pub fn balance(env: Env, id: Address) -> i128 {
@@ -50,8 +53,8 @@ impl MockToken {
         let to_balance = Self::balance(env.clone(), to.clone());
         env.storage().persistent().set(&to, &(to_balance + amount));
         
-        env.storage().persistent().extend_ttl(&from, 100, 10000);
-        env.storage().persistent().extend_ttl(&to, 100, 10000);
+        env.storage().persistent().extend_ttl(&from, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+        env.storage().persistent().extend_ttl(&to, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
     }
 
     pub fn approve(env: Env, from: Address, spender: Address, amount: i128) {
@@ -59,7 +62,7 @@ impl MockToken {
         
         let allowance_key = (from.clone(), spender.clone());
         env.storage().persistent().set(&allowance_key, &amount);
-        env.storage().persistent().extend_ttl(&allowance_key, 100, 10000);
+        env.storage().persistent().extend_ttl(&allowance_key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
     }
 
     pub fn allowance(env: Env, from: Address, spender: Address) -> i128 {
@@ -90,8 +93,8 @@ impl MockToken {
         let to_balance = Self::balance(env.clone(), to.clone());
         env.storage().persistent().set(&to, &(to_balance + amount));
         
-        env.storage().persistent().extend_ttl(&allowance_key, 100, 10000);
-        env.storage().persistent().extend_ttl(&from, 100, 10000);
-        env.storage().persistent().extend_ttl(&to, 100, 10000);
+        env.storage().persistent().extend_ttl(&allowance_key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+        env.storage().persistent().extend_ttl(&from, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+        env.storage().persistent().extend_ttl(&to, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
     }
 }
\ No newline at end of file
@@ -1,10 +1,26 @@
-use soroban_sdk::{contracttype, contracterror, Address, BytesN};
+use soroban_sdk::{contracttype, contracterror, Address, BytesN, Env, Vec, U256};
+use unite_timelocks::Timelocks;
 
 // Constants
 pub const CALLER_REWARD_PERCENTAGE: u32 = 10;
 pub const DECIMAL_FACTOR: i128 = 1_000_000; // 10^6 for 6 decimals (USDT/DAI)
 pub const EVM_DECIMAL_FACTOR: i128 = 1_000_000_000_000_000_000; // 10^18 for EVM compatibility
 
+// Storage TTLs in ledgers, about 5 seconds each
+pub const LEDGER_CLOSE_SECONDS: u64 = 5;
+pub const DAY_IN_LEDGERS: u32 = 17_280;
+pub const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+pub const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+pub const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Ledgers an entry must stay live for to outlast `timestamp` by a week, capped at the max TTL
+pub fn ttl_until(env: &Env, timestamp: u64) -> u32 {
+    let remaining = timestamp.saturating_sub(env.ledger().timestamp());
+    let ledgers = remaining / LEDGER_CLOSE_SECONDS + INSTANCE_BUMP_AMOUNT as u64;
+    ledgers.min(env.storage().max_ttl() as u64) as u32
+}
+
 // Error codes
 #[contracterror]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -25,6 +41,8 @@ pub enum Error {
     NotAllResolversDeposited = 13,
     InvalidPrice = 14,
     OrderCompleted = 15,
+    NotResolver = 16,
+    EscrowExists = 17,
 }
 
 // State enum
@@ -56,8 +74,34 @@ pub struct Order {
     pub auction_end_time: u64,
     pub start_price: u128, // Price with 18 decimals for EVM compatibility
     pub end_price: u128,
+    pub auction_details: AuctionDetails,
+}
+
+/// Shape of the Dutch auction price curve, as `AuctionDetails` in the CosmWasm `Order`
+#[contracttype]
+#[derive(Clone)]
+pub enum AuctionDetails {
+    /// Straight decay from `start_price` to `end_price`, as on the EVM
+    Linear,
+    /// Rate bumps over `end_price` in units of `unite_auction::RATE_BUMP_DENOMINATOR`:
+    /// the initial bump, then the points after it. `start_price` is not used.
+    PiecewiseLinear(u32, Vec<AuctionPoint>),
+    /// Half-life in seconds of the gap down to `end_price`, with the price falling linearly
+    /// between halvings and reaching `end_price` when the auction ends
+    Exponential(u64),
 }
 
+/// Rate bump reached `delay` seconds after the previous point, or the auction start
+#[contracttype]
+#[derive(Clone)]
+pub struct AuctionPoint {
+    pub delay: u32,
+    pub rate_bump: u32,
+}
+
+/// Most points a piecewise-linear curve may have
+pub const MAX_AUCTION_POINTS: usize = 16;
+
 // Immutables structure
 #[contracttype]
 #[derive(Clone)]
@@ -69,52 +113,35 @@ pub struct Immutables {
     pub token: Address,
     pub amount: i128, // Using i128 for Stellar token amounts
     pub safety_deposit: i128,
-    pub timelocks: u128, // Encoded timelocks
+    pub timelocks: U256, // Packed EVM timelocks, see `unite_timelocks`
 }
 
-// Timelocks helper
-#[contracttype]
-pub struct Timelocks {
-    pub deployed_at: u64,
-    pub src_withdrawal: u64,
-    pub src_public_withdrawal: u64,
-    pub src_cancellation: u64,
-    pub src_public_cancellation: u64,
-    pub dst_withdrawal: u64,
-    pub dst_public_withdrawal: u64,
-    pub dst_cancellation: u64,
+/// Decodes the packed EVM `Timelocks` word carried in `Immutables`
+pub fn unpack_timelocks(timelocks: &U256) -> Timelocks {
+    let mut word = [0u8; 32];
+    timelocks.to_be_bytes().copy_into_slice(&mut word);
+    Timelocks::from_be_bytes(word)
 }
 
-impl Timelocks {
-    pub fn decode(encoded: u128, deployed_at: u64) -> Self {
-        // Extract 32-bit values from the encoded u128
-        let src_withdrawal = (encoded & 0xFFFFFFFF) as u64;
-        let src_public_withdrawal = ((encoded >> 32) & 0xFFFFFFFF) as u64;
-        let src_cancellation = ((encoded >> 64) & 0xFFFFFFFF) as u64;
-        let src_public_cancellation = ((encoded >> 96) & 0xFFFFFFFF) as u64;
-        
-        // For higher bits, we need to work around u128 shift limitations
-        // Convert to bytes and extract manually
-        let bytes = encoded.to_be_bytes();
-        
-        // Extract dst_withdrawal (bits 128-159 -> bytes 12-15)
-        let dst_withdrawal = u32::from_be_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]) as u64;
-        
-        // Extract dst_public_withdrawal (bits 160-191 -> bytes 8-11)
-        let dst_public_withdrawal = u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as u64;
-        
-        // Extract dst_cancellation (bits 192-223 -> bytes 4-7)
-        let dst_cancellation = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as u64;
-        
-        Timelocks {
-            deployed_at,
-            src_withdrawal,
-            src_public_withdrawal,
-            src_cancellation,
-            src_public_cancellation,
-            dst_withdrawal,
-            dst_public_withdrawal,
-            dst_cancellation,
-        }
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::Env;
+    use unite_timelocks::Stage;
+
+    #[test]
+    fn test_unpack_timelocks_uses_full_word() {
+        let env = Env::default();
+        // Offsets [10, 120, 300, 400, 10, 100, 200] deployed at 1_700_000_000
+        let packed = U256::from_parts(
+            &env,
+            0x6553f100000000c8,
+            0x000000640000000a,
+            0x000001900000012c,
+            0x000000780000000a,
+        );
+        let timelocks = unpack_timelocks(&packed);
+        assert_eq!(timelocks.decode(), ([10, 120, 300, 400, 10, 100, 200], 1_700_000_000));
+        assert_eq!(timelocks.get(Stage::DstCancellation), 1_700_000_200);
     }
-}
\ No newline at end of file
+}